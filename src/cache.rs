@@ -0,0 +1,15 @@
+pub mod anim;
+pub mod container;
+pub mod config;
+pub mod group;
+pub mod interface;
+pub mod jag;
+pub mod map;
+pub mod model;
+pub mod music;
+pub mod reference;
+pub mod script;
+pub mod sound;
+pub mod sprite;
+pub mod store;
+pub mod texture;
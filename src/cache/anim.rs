@@ -0,0 +1,189 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// The value of a [scale](TransformType::Scale) axis absent from a frame, leaving the axis
+/// unscaled.
+pub const DEFAULT_SCALE: i32 = 128;
+
+/// How a group of a [FrameBase] transforms the vertices or faces carrying its labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformType {
+    /// Sets the origin later rotations and scales are relative to.
+    Origin,
+    Translate,
+    Rotate,
+    Scale,
+    /// Changes the alpha of faces rather than moving vertices.
+    Alpha,
+}
+
+impl TransformType {
+    pub fn id(self) -> u8 {
+        match self {
+            TransformType::Origin => 0,
+            TransformType::Translate => 1,
+            TransformType::Rotate => 2,
+            TransformType::Scale => 3,
+            TransformType::Alpha => 5,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<TransformType, PacketError> {
+        match id {
+            0 => Ok(TransformType::Origin),
+            1 => Ok(TransformType::Translate),
+            2 => Ok(TransformType::Rotate),
+            3 => Ok(TransformType::Scale),
+            5 => Ok(TransformType::Alpha),
+            _ => error(format!("Unknown transform type {}.", id)),
+        }
+    }
+}
+
+/// A group of a [FrameBase], applying a single kind of transform to every model label it
+/// lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransformGroup {
+    pub kind: TransformType,
+    pub labels: Vec<u8>,
+}
+
+/// The skeleton frames are applied to, stored in the bases archive.
+///
+/// - `g1` count
+/// - `g1` transform type per group
+/// - `g1` label count per group
+/// - `g1` per label per group
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameBase {
+    pub groups: Vec<TransformGroup>,
+}
+
+impl FrameBase {
+    pub fn decode(packet: &mut Packet) -> Result<FrameBase, PacketError> {
+        let count = packet.g1()? as usize;
+        let kinds = (0..count).map(|_| TransformType::from_id(packet.g1()?)).collect::<Result<Vec<_>, _>>()?;
+        let lens = (0..count).map(|_| packet.g1()).collect::<Result<Vec<_>, _>>()?;
+        let groups = kinds
+            .into_iter()
+            .zip(lens)
+            .map(|(kind, len)| {
+                let labels = (0..len).map(|_| packet.g1()).collect::<Result<_, _>>()?;
+                Ok(TransformGroup { kind, labels })
+            })
+            .collect::<Result<_, PacketError>>()?;
+        Ok(FrameBase { groups })
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if self.groups.len() > u8::MAX as usize {
+            return error(format!("Too many transform groups ({}).", self.groups.len()));
+        }
+        if let Some(group) = self.groups.iter().find(|group| group.labels.len() > u8::MAX as usize) {
+            return error(format!("Too many labels in a transform group ({}).", group.labels.len()));
+        }
+
+        packet.p1(self.groups.len() as u8);
+        self.groups.iter().for_each(|group| packet.p1(group.kind.id()));
+        self.groups.iter().for_each(|group| packet.p1(group.labels.len() as u8));
+        for group in &self.groups {
+            group.labels.iter().for_each(|label| packet.p1(*label));
+        }
+        Ok(())
+    }
+}
+
+/// The values a [Frame] applies to a group of its base, where absent axes are left unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Transform {
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub z: Option<i32>,
+}
+
+impl Transform {
+    /// Returns the values of every axis, defaulting absent axes to [DEFAULT_SCALE] for scales
+    /// and `0` otherwise.
+    pub fn values(&self, kind: TransformType) -> [i32; 3] {
+        let default = if kind == TransformType::Scale { DEFAULT_SCALE } else { 0 };
+        [self.x, self.y, self.z].map(|value| value.unwrap_or(default))
+    }
+}
+
+/// A single frame of an animation, stored in the frames archive.
+///
+/// - `g2` base id, `g1` group count
+/// - `g1` axis flags per group, where `0` leaves the group untouched
+/// - `gsmart_s16` value per set axis per group
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame {
+    pub base: u16,
+    /// The transform of each group of the base, if any.
+    pub transforms: Vec<Option<Transform>>,
+}
+
+impl Frame {
+    pub fn decode(packet: &mut Packet) -> Result<Frame, PacketError> {
+        let base = packet.g2()?;
+        let count = packet.g1()?;
+        let flags = (0..count).map(|_| packet.g1()).collect::<Result<Vec<_>, _>>()?;
+        let transforms = flags
+            .into_iter()
+            .map(|flags| {
+                if flags == 0 {
+                    return Ok(None);
+                }
+                let mut axis = |bit: u8| if flags & bit != 0 { packet.gsmart_s16().map(Some) } else { Ok(None) };
+                Ok(Some(Transform { x: axis(1)?, y: axis(2)?, z: axis(4)? }))
+            })
+            .collect::<Result<_, PacketError>>()?;
+        Ok(Frame { base, transforms })
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if self.transforms.len() > u8::MAX as usize {
+            return error(format!("Too many frame transforms ({}).", self.transforms.len()));
+        }
+        let values = self.transforms.iter().flatten().flat_map(|t| [t.x, t.y, t.z]).flatten();
+        if let Some(value) = values.clone().find(|value| !(-16384..=16383).contains(value)) {
+            return error(format!("Frame transform value {} does not fit in a smart.", value));
+        }
+
+        packet.p2(self.base);
+        packet.p1(self.transforms.len() as u8);
+        for transform in &self.transforms {
+            packet.p1(transform.map_or(0, |t| {
+                t.x.is_some() as u8 | (t.y.is_some() as u8) << 1 | (t.z.is_some() as u8) << 2
+            }));
+        }
+        values.for_each(|value| packet.psmart_s16(value));
+        Ok(())
+    }
+
+    /// Resolves the transforms applied by this frame in order, as `(group, values)`.
+    ///
+    /// The client resets the origin before every transform that is not itself an origin, using
+    /// the closest origin group since the previously transformed group.
+    pub fn resolve(&self, base: &FrameBase) -> Result<Vec<(usize, [i32; 3])>, PacketError> {
+        let mut resolved = Vec::new();
+        let mut last = None;
+        for (group, transform) in self.transforms.iter().enumerate() {
+            let Some(transform) = transform else {
+                continue;
+            };
+            let Some(kind) = base.groups.get(group).map(|group| group.kind) else {
+                return error(format!("Frame transforms group {} missing from its base.", group));
+            };
+            if kind != TransformType::Origin {
+                let start = last.map_or(0, |last| last + 1);
+                let origin = (start..group).rev().find(|i| base.groups[*i].kind == TransformType::Origin);
+                if let Some(origin) = origin {
+                    resolved.push((origin, [0, 0, 0]));
+                }
+            }
+            resolved.push((group, transform.values(kind)));
+            last = Some(group);
+        }
+        Ok(resolved)
+    }
+}
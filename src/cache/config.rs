@@ -0,0 +1,144 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+pub mod enums;
+pub mod loc;
+pub mod npc;
+pub mod obj;
+pub mod params;
+pub mod structs;
+pub mod varbit;
+
+/// The opcode terminating every config.
+pub const TERMINATOR: u8 = 0;
+
+/// The last revision where objs, npcs and locs carry an examine description under opcode `3`.
+/// Later revisions moved examines out of the cache.
+pub const DESCRIPTION_REVISION: u32 = 377;
+
+/// A config stored in the cache as a stream of opcodes, each followed by its payload, and
+/// terminated by [TERMINATOR].
+///
+/// Implementors decode a single opcode at a time, keeping the payload of every opcode they
+/// support so the config can be re-encoded. Encoding writes opcodes in ascending order, as the
+/// cache packer does, so a packed config re-encodes to the same bytes.
+pub trait ConfigType: Default {
+    /// Reads the payload of `opcode` into this config, returning an error for unknown opcodes.
+    fn decode_opcode(&mut self, opcode: u8, packet: &mut Packet, revision: u32) -> Result<(), PacketError>;
+
+    /// Writes every opcode present in this config, excluding the terminator.
+    fn encode_opcodes(&self, packet: &mut Packet, revision: u32) -> Result<(), PacketError>;
+
+    /// Decodes a config, reading opcodes until the terminator.
+    fn decode(packet: &mut Packet, revision: u32) -> Result<Self, PacketError> {
+        let mut config = Self::default();
+        loop {
+            let opcode = packet.g1()?;
+            if opcode == TERMINATOR {
+                return Ok(config);
+            }
+            config.decode_opcode(opcode, packet, revision)?;
+        }
+    }
+
+    /// Encodes this config, followed by the terminator.
+    fn encode(&self, packet: &mut Packet, revision: u32) -> Result<(), PacketError> {
+        self.encode_opcodes(packet, revision)?;
+        packet.p1(TERMINATOR);
+        Ok(())
+    }
+}
+
+/// Returns the error for an opcode not supported by the config type named `config`.
+pub(crate) fn unknown_opcode<T>(config: &str, opcode: u8) -> Result<T, PacketError> {
+    error(format!("Unknown {} opcode {}.", config, opcode))
+}
+
+/// Reads a list of colour or texture replacements: `g1` count, then `g2` find and `g2` replace
+/// per entry.
+pub(crate) fn decode_replacements(packet: &mut Packet) -> Result<Vec<(u16, u16)>, PacketError> {
+    let count = packet.g1()?;
+    (0..count).map(|_| Ok((packet.g2()?, packet.g2()?))).collect()
+}
+
+pub(crate) fn encode_replacements(packet: &mut Packet, replacements: &[(u16, u16)]) {
+    packet.p1(replacements.len() as u8);
+    for (find, replace) in replacements {
+        packet.p2(*find);
+        packet.p2(*replace);
+    }
+}
+
+/// Reads a list of ids: `g1` count, then `g2` per id.
+pub(crate) fn decode_ids(packet: &mut Packet) -> Result<Vec<u16>, PacketError> {
+    let count = packet.g1()?;
+    (0..count).map(|_| packet.g2()).collect()
+}
+
+pub(crate) fn encode_ids(packet: &mut Packet, ids: &[u16]) {
+    packet.p1(ids.len() as u8);
+    ids.iter().for_each(|id| packet.p2(*id));
+}
+
+/// The configs an npc or loc transforms into depending on the value of a varbit or varp, where
+/// the value indexes into [transforms](Multi::transforms). Ids of `65535` represent `-1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Multi {
+    pub varbit: u16,
+    pub varp: u16,
+    /// The config used when the value is out of range, only sent by the extended opcode.
+    pub default: Option<u16>,
+    pub transforms: Vec<u16>,
+}
+
+impl Multi {
+    /// Reads `g2` varbit, `g2` varp, `g2` default if `extended`, `g1` count, then `count + 1`
+    /// transforms.
+    pub(crate) fn decode(packet: &mut Packet, extended: bool) -> Result<Multi, PacketError> {
+        let varbit = packet.g2()?;
+        let varp = packet.g2()?;
+        let default = if extended { Some(packet.g2()?) } else { None };
+        let count = packet.g1()? as usize;
+        let transforms = (0..=count).map(|_| packet.g2()).collect::<Result<_, _>>()?;
+        Ok(Multi { varbit, varp, default, transforms })
+    }
+
+    /// Writes this multi under the basic or extended opcode, depending on whether a default is
+    /// present.
+    pub(crate) fn encode(&self, packet: &mut Packet, basic: u8, extended: u8) -> Result<(), PacketError> {
+        if self.transforms.is_empty() || self.transforms.len() > 256 {
+            return error(format!("Invalid multi transform count {}.", self.transforms.len()));
+        }
+        packet.p1(if self.default.is_some() { extended } else { basic });
+        packet.p2(self.varbit);
+        packet.p2(self.varp);
+        if let Some(default) = self.default {
+            packet.p2(default);
+        }
+        packet.p1((self.transforms.len() - 1) as u8);
+        self.transforms.iter().for_each(|transform| packet.p2(*transform));
+        Ok(())
+    }
+}
+
+/// Writes `opcode` followed by the value if present, using `put` to write the value.
+pub(crate) fn encode_opt<T>(packet: &mut Packet, opcode: u8, value: &Option<T>, put: impl FnOnce(&mut Packet, &T)) {
+    if let Some(value) = value {
+        packet.p1(opcode);
+        put(packet, value);
+    }
+}
+
+/// Writes `opcode` if the flag is set.
+pub(crate) fn encode_flag(packet: &mut Packet, opcode: u8, flag: bool) {
+    if flag {
+        packet.p1(opcode);
+    }
+}
+
+/// Writes each present string of `values` under consecutive opcodes starting at `first`.
+pub(crate) fn encode_strings(packet: &mut Packet, first: u8, values: &[Option<String>]) {
+    for (i, value) in values.iter().enumerate() {
+        encode_opt(packet, first + i as u8, value, |packet, value| packet.pjstr(value));
+    }
+}
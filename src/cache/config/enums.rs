@@ -0,0 +1,114 @@
+use crate::cache::config::{encode_opt, unknown_opcode, ConfigType};
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// The values of an [EnumType], keyed by integer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnumValues {
+    /// `g2` count, then `g4` key and `gjstr` value per entry.
+    Str(Vec<(i32, String)>),
+    /// `g2` count, then `g4` key and `g4` value per entry.
+    Int(Vec<(i32, i32)>),
+}
+
+/// A lookup table used by client scripts, stored in the enum config group.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnumType {
+    /// The script type char of the keys.
+    pub key_type: Option<u8>,
+    /// The script type char of the values.
+    pub value_type: Option<u8>,
+    pub default_str: Option<String>,
+    pub default_int: Option<i32>,
+    pub values: Option<EnumValues>,
+}
+
+impl EnumType {
+    /// Returns the integer value of `key`, falling back to the default when the key is absent.
+    /// Returns `None` if this enum holds strings and no default integer is set.
+    pub fn get_int(&self, key: i32) -> Option<i32> {
+        match &self.values {
+            Some(EnumValues::Int(values)) => values.iter().find(|(k, _)| *k == key).map(|(_, value)| *value),
+            _ => None,
+        }
+        .or(self.default_int)
+    }
+
+    /// Returns the string value of `key`, falling back to the default when the key is absent.
+    /// Returns `None` if this enum holds integers and no default string is set.
+    pub fn get_str(&self, key: i32) -> Option<&str> {
+        match &self.values {
+            Some(EnumValues::Str(values)) => values.iter().find(|(k, _)| *k == key).map(|(_, value)| value.as_str()),
+            _ => None,
+        }
+        .or(self.default_str.as_deref())
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.values {
+            Some(EnumValues::Str(values)) => values.len(),
+            Some(EnumValues::Int(values)) => values.len(),
+            None => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ConfigType for EnumType {
+    fn decode_opcode(&mut self, opcode: u8, packet: &mut Packet, _: u32) -> Result<(), PacketError> {
+        match opcode {
+            1 => self.key_type = Some(packet.g1()?),
+            2 => self.value_type = Some(packet.g1()?),
+            3 => self.default_str = Some(packet.gjstr()?),
+            4 => self.default_int = Some(packet.g4s()?),
+            5 => {
+                let count = packet.g2()?;
+                let values = (0..count).map(|_| Ok((packet.g4s()?, packet.gjstr()?))).collect::<Result<_, PacketError>>()?;
+                self.values = Some(EnumValues::Str(values));
+            }
+            6 => {
+                let count = packet.g2()?;
+                let values = (0..count).map(|_| Ok((packet.g4s()?, packet.g4s()?))).collect::<Result<_, PacketError>>()?;
+                self.values = Some(EnumValues::Int(values));
+            }
+            _ => return unknown_opcode("enum", opcode),
+        }
+        Ok(())
+    }
+
+    fn encode_opcodes(&self, packet: &mut Packet, _: u32) -> Result<(), PacketError> {
+        encode_opt(packet, 1, &self.key_type, |p, v| p.p1(*v));
+        encode_opt(packet, 2, &self.value_type, |p, v| p.p1(*v));
+        encode_opt(packet, 3, &self.default_str, |p, v| p.pjstr(v));
+        encode_opt(packet, 4, &self.default_int, |p, v| p.p4s(*v));
+        match &self.values {
+            Some(EnumValues::Str(values)) => {
+                if values.len() > u16::MAX as usize {
+                    return error(format!("Too many enum values ({}).", values.len()));
+                }
+                packet.p1(5);
+                packet.p2(values.len() as u16);
+                for (key, value) in values {
+                    packet.p4s(*key);
+                    packet.pjstr(value);
+                }
+            }
+            Some(EnumValues::Int(values)) => {
+                if values.len() > u16::MAX as usize {
+                    return error(format!("Too many enum values ({}).", values.len()));
+                }
+                packet.p1(6);
+                packet.p2(values.len() as u16);
+                for (key, value) in values {
+                    packet.p4s(*key);
+                    packet.p4s(*value);
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use crate::cache::config::{
+    decode_ids, decode_replacements, encode_flag, encode_ids, encode_opt, encode_replacements, encode_strings,
+    unknown_opcode, ConfigType, Multi, DESCRIPTION_REVISION,
+};
+use crate::cache::config::params::ParamMap;
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// The models of a loc, either keyed by the loc shape they are used for, or used for every
+/// shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocModels {
+    /// `g1` count, then `g2` model and `g1` shape per model.
+    Shaped(Vec<(u16, u8)>),
+    /// `g1` count, then `g2` per model.
+    Unshaped(Vec<u16>),
+}
+
+/// A sound played at random intervals around a loc.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RandomSound {
+    pub min_delay: u16,
+    pub max_delay: u16,
+    pub distance: u8,
+    pub sounds: Vec<u16>,
+}
+
+/// A scenery object definition, stored in the loc config group.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocType {
+    pub models: Option<LocModels>,
+    pub name: Option<String>,
+    /// The examine text, only present up to [DESCRIPTION_REVISION].
+    pub description: Option<String>,
+    pub width: Option<u8>,
+    pub length: Option<u8>,
+    /// Neither blocks movement nor projectiles.
+    pub not_solid: bool,
+    /// Does not block projectiles.
+    pub not_blocking_range: bool,
+    pub interactive: Option<u8>,
+    /// Skews the model to follow the height of the ground beneath it.
+    pub hillskew: bool,
+    pub share_light: bool,
+    pub occlude: bool,
+    pub anim: Option<u16>,
+    /// Blocks movement through walls only.
+    pub wall_blocking: bool,
+    pub wall_width: Option<u8>,
+    pub ambient: Option<i8>,
+    pub ops: [Option<String>; 5],
+    pub contrast: Option<i8>,
+    pub recol: Vec<(u16, u16)>,
+    pub retex: Vec<(u16, u16)>,
+    pub mirror: bool,
+    pub no_shadow: bool,
+    pub resize_x: Option<u16>,
+    pub resize_y: Option<u16>,
+    pub resize_z: Option<u16>,
+    pub mapscene: Option<u16>,
+    /// The sides of the loc from which it may be interacted with.
+    pub force_approach: Option<u8>,
+    pub offset_x: Option<i16>,
+    pub offset_y: Option<i16>,
+    pub offset_z: Option<i16>,
+    pub force_decor: bool,
+    pub break_route_finding: bool,
+    pub raise_object: Option<u8>,
+    pub multi: Option<Multi>,
+    /// A looping sound and the distance it can be heard from.
+    pub ambient_sound: Option<(u16, u8)>,
+    pub random_sound: Option<RandomSound>,
+    pub hillskew_amount: Option<u8>,
+    pub map_area: Option<u16>,
+    pub randomize_anim_start: bool,
+    pub params: ParamMap,
+}
+
+impl ConfigType for LocType {
+    fn decode_opcode(&mut self, opcode: u8, packet: &mut Packet, revision: u32) -> Result<(), PacketError> {
+        match opcode {
+            1 => {
+                let count = packet.g1()?;
+                let models = (0..count).map(|_| Ok((packet.g2()?, packet.g1()?))).collect::<Result<_, PacketError>>()?;
+                self.models = Some(LocModels::Shaped(models));
+            }
+            2 => self.name = Some(packet.gjstr()?),
+            3 if revision <= DESCRIPTION_REVISION => self.description = Some(packet.gjstr()?),
+            5 => self.models = Some(LocModels::Unshaped(decode_ids(packet)?)),
+            14 => self.width = Some(packet.g1()?),
+            15 => self.length = Some(packet.g1()?),
+            17 => self.not_solid = true,
+            18 => self.not_blocking_range = true,
+            19 => self.interactive = Some(packet.g1()?),
+            21 => self.hillskew = true,
+            22 => self.share_light = true,
+            23 => self.occlude = true,
+            24 => self.anim = Some(packet.g2()?),
+            27 => self.wall_blocking = true,
+            28 => self.wall_width = Some(packet.g1()?),
+            29 => self.ambient = Some(packet.g1s()?),
+            30..=34 => self.ops[(opcode - 30) as usize] = Some(packet.gjstr()?),
+            39 => self.contrast = Some(packet.g1s()?),
+            40 => self.recol = decode_replacements(packet)?,
+            41 => self.retex = decode_replacements(packet)?,
+            62 => self.mirror = true,
+            64 => self.no_shadow = true,
+            65 => self.resize_x = Some(packet.g2()?),
+            66 => self.resize_y = Some(packet.g2()?),
+            67 => self.resize_z = Some(packet.g2()?),
+            68 => self.mapscene = Some(packet.g2()?),
+            69 => self.force_approach = Some(packet.g1()?),
+            70 => self.offset_x = Some(packet.g2s()?),
+            71 => self.offset_y = Some(packet.g2s()?),
+            72 => self.offset_z = Some(packet.g2s()?),
+            73 => self.force_decor = true,
+            74 => self.break_route_finding = true,
+            75 => self.raise_object = Some(packet.g1()?),
+            77 | 92 => self.multi = Some(Multi::decode(packet, opcode == 92)?),
+            78 => self.ambient_sound = Some((packet.g2()?, packet.g1()?)),
+            79 => {
+                let min_delay = packet.g2()?;
+                let max_delay = packet.g2()?;
+                let distance = packet.g1()?;
+                let sounds = decode_ids(packet)?;
+                self.random_sound = Some(RandomSound { min_delay, max_delay, distance, sounds });
+            }
+            81 => self.hillskew_amount = Some(packet.g1()?),
+            82 => self.map_area = Some(packet.g2()?),
+            89 => self.randomize_anim_start = true,
+            249 => self.params = ParamMap::decode(packet)?,
+            _ => return unknown_opcode("loc", opcode),
+        }
+        Ok(())
+    }
+
+    fn encode_opcodes(&self, packet: &mut Packet, revision: u32) -> Result<(), PacketError> {
+        if let Some(LocModels::Shaped(models)) = &self.models {
+            if models.len() > u8::MAX as usize {
+                return error(format!("Too many loc models ({}).", models.len()));
+            }
+            packet.p1(1);
+            packet.p1(models.len() as u8);
+            for (model, shape) in models {
+                packet.p2(*model);
+                packet.p1(*shape);
+            }
+        }
+        encode_opt(packet, 2, &self.name, |p, v| p.pjstr(v));
+        if revision <= DESCRIPTION_REVISION {
+            encode_opt(packet, 3, &self.description, |p, v| p.pjstr(v));
+        }
+        if let Some(LocModels::Unshaped(models)) = &self.models {
+            packet.p1(5);
+            encode_ids(packet, models);
+        }
+        encode_opt(packet, 14, &self.width, |p, v| p.p1(*v));
+        encode_opt(packet, 15, &self.length, |p, v| p.p1(*v));
+        encode_flag(packet, 17, self.not_solid);
+        encode_flag(packet, 18, self.not_blocking_range);
+        encode_opt(packet, 19, &self.interactive, |p, v| p.p1(*v));
+        encode_flag(packet, 21, self.hillskew);
+        encode_flag(packet, 22, self.share_light);
+        encode_flag(packet, 23, self.occlude);
+        encode_opt(packet, 24, &self.anim, |p, v| p.p2(*v));
+        encode_flag(packet, 27, self.wall_blocking);
+        encode_opt(packet, 28, &self.wall_width, |p, v| p.p1(*v));
+        encode_opt(packet, 29, &self.ambient, |p, v| p.p1s(*v));
+        encode_strings(packet, 30, &self.ops);
+        encode_opt(packet, 39, &self.contrast, |p, v| p.p1s(*v));
+        if !self.recol.is_empty() {
+            packet.p1(40);
+            encode_replacements(packet, &self.recol);
+        }
+        if !self.retex.is_empty() {
+            packet.p1(41);
+            encode_replacements(packet, &self.retex);
+        }
+        encode_flag(packet, 62, self.mirror);
+        encode_flag(packet, 64, self.no_shadow);
+        encode_opt(packet, 65, &self.resize_x, |p, v| p.p2(*v));
+        encode_opt(packet, 66, &self.resize_y, |p, v| p.p2(*v));
+        encode_opt(packet, 67, &self.resize_z, |p, v| p.p2(*v));
+        encode_opt(packet, 68, &self.mapscene, |p, v| p.p2(*v));
+        encode_opt(packet, 69, &self.force_approach, |p, v| p.p1(*v));
+        encode_opt(packet, 70, &self.offset_x, |p, v| p.p2s(*v));
+        encode_opt(packet, 71, &self.offset_y, |p, v| p.p2s(*v));
+        encode_opt(packet, 72, &self.offset_z, |p, v| p.p2s(*v));
+        encode_flag(packet, 73, self.force_decor);
+        encode_flag(packet, 74, self.break_route_finding);
+        encode_opt(packet, 75, &self.raise_object, |p, v| p.p1(*v));
+        if let Some(multi) = self.multi.as_ref().filter(|multi| multi.default.is_none()) {
+            multi.encode(packet, 77, 92)?;
+        }
+        encode_opt(packet, 78, &self.ambient_sound, |p, (sound, distance)| {
+            p.p2(*sound);
+            p.p1(*distance);
+        });
+        encode_opt(packet, 79, &self.random_sound, |p, v| {
+            p.p2(v.min_delay);
+            p.p2(v.max_delay);
+            p.p1(v.distance);
+            encode_ids(p, &v.sounds);
+        });
+        encode_opt(packet, 81, &self.hillskew_amount, |p, v| p.p1(*v));
+        encode_opt(packet, 82, &self.map_area, |p, v| p.p2(*v));
+        encode_flag(packet, 89, self.randomize_anim_start);
+        if let Some(multi) = self.multi.as_ref().filter(|multi| multi.default.is_some()) {
+            multi.encode(packet, 77, 92)?;
+        }
+        if !self.params.is_empty() {
+            packet.p1(249);
+            self.params.encode(packet)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use crate::cache::config::{
+    decode_ids, decode_replacements, encode_flag, encode_ids, encode_opt, encode_replacements, encode_strings,
+    unknown_opcode, ConfigType, Multi, DESCRIPTION_REVISION,
+};
+use crate::cache::config::params::ParamMap;
+use crate::packet::bytes::Packet;
+use crate::packet::error::PacketError;
+
+/// The animations an npc uses while walking: forwards, turning around, and strafing left and
+/// right.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WalkAnimations {
+    pub walk: u16,
+    pub turn_around: u16,
+    pub turn_left: u16,
+    pub turn_right: u16,
+}
+
+/// An npc definition, stored in the npc config group.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NpcType {
+    pub models: Vec<u16>,
+    pub name: Option<String>,
+    /// The examine text, only present up to [DESCRIPTION_REVISION].
+    pub description: Option<String>,
+    pub size: Option<u8>,
+    pub ready_anim: Option<u16>,
+    pub walk_anim: Option<u16>,
+    pub turn_left_anim: Option<u16>,
+    pub turn_right_anim: Option<u16>,
+    pub walk_anims: Option<WalkAnimations>,
+    pub category: Option<u16>,
+    pub ops: [Option<String>; 5],
+    pub recol: Vec<(u16, u16)>,
+    pub retex: Vec<(u16, u16)>,
+    pub head_models: Vec<u16>,
+    /// Hides the npc's dot on the minimap.
+    pub hide_on_minimap: bool,
+    pub combat_level: Option<u16>,
+    pub resize_h: Option<u16>,
+    pub resize_v: Option<u16>,
+    /// Draws the npc above others occupying the same tile.
+    pub render_priority: bool,
+    pub ambient: Option<i8>,
+    pub contrast: Option<i8>,
+    pub head_icon: Option<u16>,
+    pub turn_speed: Option<u16>,
+    pub multi: Option<Multi>,
+    /// Prevents the npc from being interacted with.
+    pub not_interactable: bool,
+    /// Prevents the npc from turning to face its target.
+    pub not_rotating: bool,
+    pub follower: bool,
+    pub params: ParamMap,
+}
+
+impl ConfigType for NpcType {
+    fn decode_opcode(&mut self, opcode: u8, packet: &mut Packet, revision: u32) -> Result<(), PacketError> {
+        match opcode {
+            1 => self.models = decode_ids(packet)?,
+            2 => self.name = Some(packet.gjstr()?),
+            3 if revision <= DESCRIPTION_REVISION => self.description = Some(packet.gjstr()?),
+            12 => self.size = Some(packet.g1()?),
+            13 => self.ready_anim = Some(packet.g2()?),
+            14 => self.walk_anim = Some(packet.g2()?),
+            15 => self.turn_left_anim = Some(packet.g2()?),
+            16 => self.turn_right_anim = Some(packet.g2()?),
+            17 => {
+                self.walk_anims = Some(WalkAnimations {
+                    walk: packet.g2()?,
+                    turn_around: packet.g2()?,
+                    turn_left: packet.g2()?,
+                    turn_right: packet.g2()?,
+                })
+            }
+            18 => self.category = Some(packet.g2()?),
+            30..=34 => self.ops[(opcode - 30) as usize] = Some(packet.gjstr()?),
+            40 => self.recol = decode_replacements(packet)?,
+            41 => self.retex = decode_replacements(packet)?,
+            60 => self.head_models = decode_ids(packet)?,
+            93 => self.hide_on_minimap = true,
+            95 => self.combat_level = Some(packet.g2()?),
+            97 => self.resize_h = Some(packet.g2()?),
+            98 => self.resize_v = Some(packet.g2()?),
+            99 => self.render_priority = true,
+            100 => self.ambient = Some(packet.g1s()?),
+            101 => self.contrast = Some(packet.g1s()?),
+            102 => self.head_icon = Some(packet.g2()?),
+            103 => self.turn_speed = Some(packet.g2()?),
+            106 | 118 => self.multi = Some(Multi::decode(packet, opcode == 118)?),
+            107 => self.not_interactable = true,
+            109 => self.not_rotating = true,
+            111 => self.follower = true,
+            249 => self.params = ParamMap::decode(packet)?,
+            _ => return unknown_opcode("npc", opcode),
+        }
+        Ok(())
+    }
+
+    fn encode_opcodes(&self, packet: &mut Packet, revision: u32) -> Result<(), PacketError> {
+        if !self.models.is_empty() {
+            packet.p1(1);
+            encode_ids(packet, &self.models);
+        }
+        encode_opt(packet, 2, &self.name, |p, v| p.pjstr(v));
+        if revision <= DESCRIPTION_REVISION {
+            encode_opt(packet, 3, &self.description, |p, v| p.pjstr(v));
+        }
+        encode_opt(packet, 12, &self.size, |p, v| p.p1(*v));
+        encode_opt(packet, 13, &self.ready_anim, |p, v| p.p2(*v));
+        encode_opt(packet, 14, &self.walk_anim, |p, v| p.p2(*v));
+        encode_opt(packet, 15, &self.turn_left_anim, |p, v| p.p2(*v));
+        encode_opt(packet, 16, &self.turn_right_anim, |p, v| p.p2(*v));
+        encode_opt(packet, 17, &self.walk_anims, |p, v| {
+            p.p2(v.walk);
+            p.p2(v.turn_around);
+            p.p2(v.turn_left);
+            p.p2(v.turn_right);
+        });
+        encode_opt(packet, 18, &self.category, |p, v| p.p2(*v));
+        encode_strings(packet, 30, &self.ops);
+        if !self.recol.is_empty() {
+            packet.p1(40);
+            encode_replacements(packet, &self.recol);
+        }
+        if !self.retex.is_empty() {
+            packet.p1(41);
+            encode_replacements(packet, &self.retex);
+        }
+        if !self.head_models.is_empty() {
+            packet.p1(60);
+            encode_ids(packet, &self.head_models);
+        }
+        encode_flag(packet, 93, self.hide_on_minimap);
+        encode_opt(packet, 95, &self.combat_level, |p, v| p.p2(*v));
+        encode_opt(packet, 97, &self.resize_h, |p, v| p.p2(*v));
+        encode_opt(packet, 98, &self.resize_v, |p, v| p.p2(*v));
+        encode_flag(packet, 99, self.render_priority);
+        encode_opt(packet, 100, &self.ambient, |p, v| p.p1s(*v));
+        encode_opt(packet, 101, &self.contrast, |p, v| p.p1s(*v));
+        encode_opt(packet, 102, &self.head_icon, |p, v| p.p2(*v));
+        encode_opt(packet, 103, &self.turn_speed, |p, v| p.p2(*v));
+        if let Some(multi) = self.multi.as_ref().filter(|multi| multi.default.is_none()) {
+            multi.encode(packet, 106, 118)?;
+        }
+        encode_flag(packet, 107, self.not_interactable);
+        encode_flag(packet, 109, self.not_rotating);
+        encode_flag(packet, 111, self.follower);
+        if let Some(multi) = self.multi.as_ref().filter(|multi| multi.default.is_some()) {
+            multi.encode(packet, 106, 118)?;
+        }
+        if !self.params.is_empty() {
+            packet.p1(249);
+            self.params.encode(packet)?;
+        }
+        Ok(())
+    }
+}
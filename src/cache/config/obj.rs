@@ -0,0 +1,234 @@
+use crate::cache::config::{
+    decode_replacements, encode_flag, encode_opt, encode_replacements, encode_strings, unknown_opcode,
+    ConfigType, DESCRIPTION_REVISION,
+};
+use crate::cache::config::params::ParamMap;
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// An item definition, stored in the obj config group.
+///
+/// Supports the opcodes read by Old School clients, along with the examine carried by revisions
+/// up to [DESCRIPTION_REVISION]. Any other opcode is rejected as unknown.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjType {
+    pub model: Option<u16>,
+    pub name: Option<String>,
+    /// The examine text, only present up to [DESCRIPTION_REVISION].
+    pub description: Option<String>,
+    pub zoom2d: Option<u16>,
+    pub xan2d: Option<u16>,
+    pub yan2d: Option<u16>,
+    pub xoff2d: Option<i16>,
+    pub yoff2d: Option<i16>,
+    /// An unidentified string under opcode `9`, kept so the obj re-encodes unchanged.
+    pub unknown_text: Option<String>,
+    pub stackable: bool,
+    pub cost: Option<i32>,
+    /// The equipment slot the obj is worn in.
+    pub wear_pos0: Option<u8>,
+    /// The equipment slots hidden while the obj is worn.
+    pub wear_pos1: Option<u8>,
+    pub wear_pos2: Option<u8>,
+    pub members: bool,
+    /// The primary male worn model and its vertical offset.
+    pub male_model0: Option<(u16, u8)>,
+    pub male_model1: Option<u16>,
+    /// The primary female worn model and its vertical offset.
+    pub female_model0: Option<(u16, u8)>,
+    pub female_model1: Option<u16>,
+    /// The options shown when the item is on the ground.
+    pub ops: [Option<String>; 5],
+    /// The options shown when the item is in the inventory.
+    pub iops: [Option<String>; 5],
+    pub recol: Vec<(u16, u16)>,
+    pub retex: Vec<(u16, u16)>,
+    pub shift_click_index: Option<i8>,
+    /// The sub options of each inventory option, as `(index, text)` in the order they are sent.
+    pub subops: [Vec<(u8, String)>; 5],
+    pub tradeable: bool,
+    /// The weight in grams.
+    pub weight: Option<i16>,
+    pub male_model2: Option<u16>,
+    pub female_model2: Option<u16>,
+    pub male_head0: Option<u16>,
+    pub female_head0: Option<u16>,
+    pub male_head1: Option<u16>,
+    pub female_head1: Option<u16>,
+    pub category: Option<u16>,
+    pub zan2d: Option<u16>,
+    /// The obj this is the noted or unnoted counterpart of.
+    pub cert_link: Option<u16>,
+    pub cert_template: Option<u16>,
+    /// The objs displayed once the stack size reaches the given count.
+    pub count: [Option<(u16, u16)>; 10],
+    pub resize_x: Option<u16>,
+    pub resize_y: Option<u16>,
+    pub resize_z: Option<u16>,
+    pub ambient: Option<i8>,
+    pub contrast: Option<i8>,
+    pub team: Option<u8>,
+    pub bought_link: Option<u16>,
+    pub bought_template: Option<u16>,
+    pub placeholder_link: Option<u16>,
+    pub placeholder_template: Option<u16>,
+    pub params: ParamMap,
+}
+
+impl ConfigType for ObjType {
+    fn decode_opcode(&mut self, opcode: u8, packet: &mut Packet, revision: u32) -> Result<(), PacketError> {
+        match opcode {
+            1 => self.model = Some(packet.g2()?),
+            2 => self.name = Some(packet.gjstr()?),
+            3 if revision <= DESCRIPTION_REVISION => self.description = Some(packet.gjstr()?),
+            4 => self.zoom2d = Some(packet.g2()?),
+            5 => self.xan2d = Some(packet.g2()?),
+            6 => self.yan2d = Some(packet.g2()?),
+            7 => self.xoff2d = Some(packet.g2s()?),
+            8 => self.yoff2d = Some(packet.g2s()?),
+            9 => self.unknown_text = Some(packet.gjstr()?),
+            11 => self.stackable = true,
+            12 => self.cost = Some(packet.g4s()?),
+            13 => self.wear_pos0 = Some(packet.g1()?),
+            14 => self.wear_pos1 = Some(packet.g1()?),
+            16 => self.members = true,
+            23 => self.male_model0 = Some((packet.g2()?, packet.g1()?)),
+            24 => self.male_model1 = Some(packet.g2()?),
+            25 => self.female_model0 = Some((packet.g2()?, packet.g1()?)),
+            26 => self.female_model1 = Some(packet.g2()?),
+            27 => self.wear_pos2 = Some(packet.g1()?),
+            30..=34 => self.ops[(opcode - 30) as usize] = Some(packet.gjstr()?),
+            35..=39 => self.iops[(opcode - 35) as usize] = Some(packet.gjstr()?),
+            40 => self.recol = decode_replacements(packet)?,
+            41 => self.retex = decode_replacements(packet)?,
+            42 => self.shift_click_index = Some(packet.g1s()?),
+            43 => {
+                // The client reads and discards the sub options of an invalid option.
+                let op = packet.g1()? as usize;
+                let mut subops = Vec::new();
+                loop {
+                    let index = packet.g1()?;
+                    if index == 0 {
+                        break;
+                    }
+                    subops.push((index - 1, packet.gjstr()?));
+                }
+                if let Some(existing) = self.subops.get_mut(op) {
+                    existing.extend(subops);
+                }
+            }
+            65 => self.tradeable = true,
+            75 => self.weight = Some(packet.g2s()?),
+            78 => self.male_model2 = Some(packet.g2()?),
+            79 => self.female_model2 = Some(packet.g2()?),
+            90 => self.male_head0 = Some(packet.g2()?),
+            91 => self.female_head0 = Some(packet.g2()?),
+            92 => self.male_head1 = Some(packet.g2()?),
+            93 => self.female_head1 = Some(packet.g2()?),
+            94 => self.category = Some(packet.g2()?),
+            95 => self.zan2d = Some(packet.g2()?),
+            97 => self.cert_link = Some(packet.g2()?),
+            98 => self.cert_template = Some(packet.g2()?),
+            100..=109 => self.count[(opcode - 100) as usize] = Some((packet.g2()?, packet.g2()?)),
+            110 => self.resize_x = Some(packet.g2()?),
+            111 => self.resize_y = Some(packet.g2()?),
+            112 => self.resize_z = Some(packet.g2()?),
+            113 => self.ambient = Some(packet.g1s()?),
+            114 => self.contrast = Some(packet.g1s()?),
+            115 => self.team = Some(packet.g1()?),
+            139 => self.bought_link = Some(packet.g2()?),
+            140 => self.bought_template = Some(packet.g2()?),
+            148 => self.placeholder_link = Some(packet.g2()?),
+            149 => self.placeholder_template = Some(packet.g2()?),
+            249 => self.params = ParamMap::decode(packet)?,
+            _ => return unknown_opcode("obj", opcode),
+        }
+        Ok(())
+    }
+
+    fn encode_opcodes(&self, packet: &mut Packet, revision: u32) -> Result<(), PacketError> {
+        encode_opt(packet, 1, &self.model, |p, v| p.p2(*v));
+        encode_opt(packet, 2, &self.name, |p, v| p.pjstr(v));
+        if revision <= DESCRIPTION_REVISION {
+            encode_opt(packet, 3, &self.description, |p, v| p.pjstr(v));
+        }
+        encode_opt(packet, 4, &self.zoom2d, |p, v| p.p2(*v));
+        encode_opt(packet, 5, &self.xan2d, |p, v| p.p2(*v));
+        encode_opt(packet, 6, &self.yan2d, |p, v| p.p2(*v));
+        encode_opt(packet, 7, &self.xoff2d, |p, v| p.p2s(*v));
+        encode_opt(packet, 8, &self.yoff2d, |p, v| p.p2s(*v));
+        encode_opt(packet, 9, &self.unknown_text, |p, v| p.pjstr(v));
+        encode_flag(packet, 11, self.stackable);
+        encode_opt(packet, 12, &self.cost, |p, v| p.p4s(*v));
+        encode_opt(packet, 13, &self.wear_pos0, |p, v| p.p1(*v));
+        encode_opt(packet, 14, &self.wear_pos1, |p, v| p.p1(*v));
+        encode_flag(packet, 16, self.members);
+        encode_opt(packet, 23, &self.male_model0, |p, (model, offset)| {
+            p.p2(*model);
+            p.p1(*offset);
+        });
+        encode_opt(packet, 24, &self.male_model1, |p, v| p.p2(*v));
+        encode_opt(packet, 25, &self.female_model0, |p, (model, offset)| {
+            p.p2(*model);
+            p.p1(*offset);
+        });
+        encode_opt(packet, 26, &self.female_model1, |p, v| p.p2(*v));
+        encode_opt(packet, 27, &self.wear_pos2, |p, v| p.p1(*v));
+        encode_strings(packet, 30, &self.ops);
+        encode_strings(packet, 35, &self.iops);
+        if !self.recol.is_empty() {
+            packet.p1(40);
+            encode_replacements(packet, &self.recol);
+        }
+        if !self.retex.is_empty() {
+            packet.p1(41);
+            encode_replacements(packet, &self.retex);
+        }
+        encode_opt(packet, 42, &self.shift_click_index, |p, v| p.p1s(*v));
+        for (op, subops) in self.subops.iter().enumerate().filter(|(_, subops)| !subops.is_empty()) {
+            if let Some((index, _)) = subops.iter().find(|(index, _)| *index == u8::MAX) {
+                return error(format!("Sub option index {} is out of range.", index));
+            }
+            packet.p1(43);
+            packet.p1(op as u8);
+            for (index, text) in subops {
+                packet.p1(index + 1);
+                packet.pjstr(text);
+            }
+            packet.p1(0);
+        }
+        encode_flag(packet, 65, self.tradeable);
+        encode_opt(packet, 75, &self.weight, |p, v| p.p2s(*v));
+        encode_opt(packet, 78, &self.male_model2, |p, v| p.p2(*v));
+        encode_opt(packet, 79, &self.female_model2, |p, v| p.p2(*v));
+        encode_opt(packet, 90, &self.male_head0, |p, v| p.p2(*v));
+        encode_opt(packet, 91, &self.female_head0, |p, v| p.p2(*v));
+        encode_opt(packet, 92, &self.male_head1, |p, v| p.p2(*v));
+        encode_opt(packet, 93, &self.female_head1, |p, v| p.p2(*v));
+        encode_opt(packet, 94, &self.category, |p, v| p.p2(*v));
+        encode_opt(packet, 95, &self.zan2d, |p, v| p.p2(*v));
+        encode_opt(packet, 97, &self.cert_link, |p, v| p.p2(*v));
+        encode_opt(packet, 98, &self.cert_template, |p, v| p.p2(*v));
+        for (i, count) in self.count.iter().enumerate() {
+            encode_opt(packet, 100 + i as u8, count, |p, (obj, count)| {
+                p.p2(*obj);
+                p.p2(*count);
+            });
+        }
+        encode_opt(packet, 110, &self.resize_x, |p, v| p.p2(*v));
+        encode_opt(packet, 111, &self.resize_y, |p, v| p.p2(*v));
+        encode_opt(packet, 112, &self.resize_z, |p, v| p.p2(*v));
+        encode_opt(packet, 113, &self.ambient, |p, v| p.p1s(*v));
+        encode_opt(packet, 114, &self.contrast, |p, v| p.p1s(*v));
+        encode_opt(packet, 115, &self.team, |p, v| p.p1(*v));
+        encode_opt(packet, 139, &self.bought_link, |p, v| p.p2(*v));
+        encode_opt(packet, 140, &self.bought_template, |p, v| p.p2(*v));
+        encode_opt(packet, 148, &self.placeholder_link, |p, v| p.p2(*v));
+        encode_opt(packet, 149, &self.placeholder_template, |p, v| p.p2(*v));
+        if !self.params.is_empty() {
+            packet.p1(249);
+            self.params.encode(packet)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// A value within a [ParamMap].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamValue {
+    Int(i32),
+    Str(String),
+}
+
+/// The params attached to objs, npcs, locs and structs, read by client scripts by key.
+///
+/// - `g1` count, then `g1` is string, `g3` key and `gjstr` or `g4` value per param
+///
+/// Params are kept in the order they were decoded or inserted so a decoded map re-encodes to the
+/// same bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamMap {
+    params: Vec<(u32, ParamValue)>,
+}
+
+impl ParamMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    pub fn get(&self, key: u32) -> Option<&ParamValue> {
+        self.params.iter().find(|(k, _)| *k == key).map(|(_, value)| value)
+    }
+
+    /// Returns the value of an integer param, or `None` if absent or a string.
+    pub fn get_int(&self, key: u32) -> Option<i32> {
+        match self.get(key) {
+            Some(ParamValue::Int(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a string param, or `None` if absent or an integer.
+    pub fn get_str(&self, key: u32) -> Option<&str> {
+        match self.get(key) {
+            Some(ParamValue::Str(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Sets the value of a param, replacing the existing value in place if present.
+    pub fn insert(&mut self, key: u32, value: ParamValue) {
+        match self.params.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.params.push((key, value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: u32) -> Option<ParamValue> {
+        let index = self.params.iter().position(|(k, _)| *k == key)?;
+        Some(self.params.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &ParamValue)> {
+        self.params.iter().map(|(key, value)| (*key, value))
+    }
+
+    pub fn decode(packet: &mut Packet) -> Result<ParamMap, PacketError> {
+        let count = packet.g1()?;
+        let mut params = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let is_string = packet.g1()? == 1;
+            let key = packet.g3()? as u32;
+            let value = if is_string {
+                ParamValue::Str(packet.gjstr()?)
+            } else {
+                ParamValue::Int(packet.g4s()?)
+            };
+            params.push((key, value));
+        }
+        Ok(ParamMap { params })
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if self.params.len() > u8::MAX as usize {
+            return error(format!("Too many params ({}).", self.params.len()));
+        }
+
+        packet.p1(self.params.len() as u8);
+        for (key, value) in &self.params {
+            if *key > 0xFFFFFF {
+                return error(format!("Param key {} does not fit in 24 bits.", key));
+            }
+            packet.p1(matches!(value, ParamValue::Str(_)) as u8);
+            packet.p3(*key);
+            match value {
+                ParamValue::Int(value) => packet.p4s(*value),
+                ParamValue::Str(value) => packet.pjstr(value),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<(u32, ParamValue)> for ParamMap {
+    fn from_iter<T: IntoIterator<Item = (u32, ParamValue)>>(iter: T) -> Self {
+        let mut params = ParamMap::new();
+        iter.into_iter().for_each(|(key, value)| params.insert(key, value));
+        params
+    }
+}
@@ -0,0 +1,39 @@
+use crate::cache::config::{unknown_opcode, ConfigType};
+use crate::cache::config::params::ParamMap;
+use crate::packet::bytes::Packet;
+use crate::packet::error::PacketError;
+
+/// A set of params grouped together for use by client scripts, stored in the struct config
+/// group.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StructType {
+    pub params: ParamMap,
+}
+
+impl StructType {
+    pub fn get_int(&self, key: u32) -> Option<i32> {
+        self.params.get_int(key)
+    }
+
+    pub fn get_str(&self, key: u32) -> Option<&str> {
+        self.params.get_str(key)
+    }
+}
+
+impl ConfigType for StructType {
+    fn decode_opcode(&mut self, opcode: u8, packet: &mut Packet, _: u32) -> Result<(), PacketError> {
+        match opcode {
+            249 => self.params = ParamMap::decode(packet)?,
+            _ => return unknown_opcode("struct", opcode),
+        }
+        Ok(())
+    }
+
+    fn encode_opcodes(&self, packet: &mut Packet, _: u32) -> Result<(), PacketError> {
+        if !self.params.is_empty() {
+            packet.p1(249);
+            self.params.encode(packet)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use crate::cache::config::{unknown_opcode, ConfigType};
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// A range of bits within a varp, stored in the varbit config group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VarBitType {
+    pub varp: u16,
+    /// The index of the least significant bit.
+    pub start: u8,
+    /// The index of the most significant bit.
+    pub end: u8,
+}
+
+impl VarBitType {
+    /// Extracts the value of this varbit from the value of its varp. Decoded varbits always lie
+    /// within the 32 bits of the varp; for one constructed past them, the missing bits read as
+    /// `0` rather than panicking.
+    pub fn get(&self, varp_value: i32) -> i32 {
+        let width = (self.end as u32 + 1).saturating_sub(self.start as u32).min(32);
+        let mask = if width == 32 { u32::MAX } else { (1u32 << width) - 1 };
+        ((varp_value as u32).checked_shr(self.start as u32).unwrap_or(0) & mask) as i32
+    }
+}
+
+impl ConfigType for VarBitType {
+    fn decode_opcode(&mut self, opcode: u8, packet: &mut Packet, _: u32) -> Result<(), PacketError> {
+        match opcode {
+            1 => {
+                self.varp = packet.g2()?;
+                self.start = packet.g1()?;
+                self.end = packet.g1()?;
+                if self.start > 31 || self.end > 31 {
+                    return error(format!("Varbit bits {}..={} exceed the 32 bits of a varp.", self.start, self.end));
+                }
+            }
+            _ => return unknown_opcode("varbit", opcode),
+        }
+        Ok(())
+    }
+
+    fn encode_opcodes(&self, packet: &mut Packet, _: u32) -> Result<(), PacketError> {
+        if self.start > 31 || self.end > 31 {
+            return error(format!("Varbit bits {}..={} exceed the 32 bits of a varp.", self.start, self.end));
+        }
+        packet.p1(1);
+        packet.p2(self.varp);
+        packet.p1(self.start);
+        packet.p1(self.end);
+        Ok(())
+    }
+}
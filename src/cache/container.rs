@@ -0,0 +1,201 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::io::{Read, Write};
+
+/// The header prepended to bzip2 streams which the cache omits from the stored data.
+const BZIP2_HEADER: &[u8; 4] = b"BZh1";
+
+/// The compression applied to the payload of a [Container].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Compression {
+    None = 0,
+    Bzip2 = 1,
+    Gzip = 2,
+    /// Only supported when the `lzma` feature is enabled.
+    Lzma = 3,
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = PacketError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Bzip2),
+            2 => Ok(Compression::Gzip),
+            3 => Ok(Compression::Lzma),
+            _ => error(format!("Unknown container compression type {}.", value)),
+        }
+    }
+}
+
+impl Compression {
+    /// Compresses `data` into the form stored within a container.
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, PacketError> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Bzip2 => {
+                let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::new(1));
+                encoder.write_all(data)?;
+                let mut compressed = encoder.finish()?;
+                compressed.drain(..BZIP2_HEADER.len());
+                Ok(compressed)
+            }
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::Lzma => lzma_compress(data),
+        }
+    }
+
+    /// Decompresses a payload stored within a container, verifying that the result is exactly
+    /// `len` bytes long.
+    pub fn decompress(&self, data: &[u8], len: usize) -> Result<Vec<u8>, PacketError> {
+        // The length comes from the container header, so it is only trusted once verified.
+        let mut decompressed = Vec::new();
+        match self {
+            Compression::None => decompressed.extend_from_slice(data),
+            Compression::Bzip2 => {
+                bzip2::read::BzDecoder::new(BZIP2_HEADER.chain(data)).read_to_end(&mut decompressed)?;
+            }
+            Compression::Gzip => {
+                flate2::read::GzDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            Compression::Lzma => decompressed = lzma_decompress(data, len)?,
+        }
+
+        if decompressed.len() != len {
+            return error(format!(
+                "Decompressed length mismatch. Expected {}, got {}.",
+                len,
+                decompressed.len()
+            ));
+        }
+        Ok(decompressed)
+    }
+}
+
+#[cfg(feature = "lzma")]
+fn lzma_compress(data: &[u8]) -> Result<Vec<u8>, PacketError> {
+    use lzma_rs::compress::{Options, UnpackedSize};
+
+    let mut compressed = Vec::new();
+    let options = Options { unpacked_size: UnpackedSize::SkipWritingToHeader };
+    lzma_rs::lzma_compress_with_options(&mut &data[..], &mut compressed, &options)?;
+    Ok(compressed)
+}
+
+#[cfg(feature = "lzma")]
+fn lzma_decompress(data: &[u8], len: usize) -> Result<Vec<u8>, PacketError> {
+    use lzma_rs::decompress::{Options, UnpackedSize};
+
+    let mut decompressed = Vec::new();
+    let options = Options {
+        unpacked_size: UnpackedSize::UseProvided(Some(len as u64)),
+        ..Default::default()
+    };
+    match lzma_rs::lzma_decompress_with_options(&mut &data[..], &mut decompressed, &options) {
+        Ok(_) => Ok(decompressed),
+        Err(e) => error(format!("Failed to decompress lzma container: {}", e)),
+    }
+}
+
+#[cfg(not(feature = "lzma"))]
+fn lzma_compress(_: &[u8]) -> Result<Vec<u8>, PacketError> {
+    error("LZMA compression requires the `lzma` feature.".to_string())
+}
+
+#[cfg(not(feature = "lzma"))]
+fn lzma_decompress(_: &[u8], _: usize) -> Result<Vec<u8>, PacketError> {
+    error("LZMA compression requires the `lzma` feature.".to_string())
+}
+
+/// A cache file as stored within the cache: a compression type, the compressed length, the
+/// uncompressed length (when compressed), the payload and an optional trailing version.
+///
+/// Everything following the compressed length may be encrypted with an XTEA key. An all-zero
+/// key is treated the same as no key at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    pub compression: Compression,
+    pub data: Vec<u8>,
+    pub version: Option<u16>,
+}
+
+impl Container {
+    pub fn new(compression: Compression, data: Vec<u8>) -> Self {
+        Self {
+            compression,
+            data,
+            version: None,
+        }
+    }
+
+    /// Decodes a container starting at the packet's current position, decrypting it in place
+    /// with `key` if one is given. A version is read if at least two bytes remain after the
+    /// payload.
+    pub fn decode(packet: &mut Packet, key: Option<[i32; 4]>) -> Result<Container, PacketError> {
+        let compression = Compression::try_from(packet.g1()?)?;
+        let compressed_len = packet.g4()? as usize;
+        let encrypted_len = match compression {
+            Compression::None => compressed_len,
+            _ => compressed_len + 4,
+        };
+
+        if !packet.has_available(encrypted_len) {
+            return error(format!(
+                "Not enough data for container. Needed {}, have {}.",
+                encrypted_len,
+                packet.available_count()
+            ));
+        }
+
+        if let Some(key) = key.filter(|key| *key != [0; 4]) {
+            let start = packet.get_pos();
+            packet.tiny_key_decrypt_range(&key, start, start + encrypted_len)?;
+        }
+
+        let data = match compression {
+            Compression::None => packet.gdata(compressed_len),
+            _ => {
+                let len = packet.g4()? as usize;
+                let compressed = packet.gdata(compressed_len);
+                compression.decompress(&compressed, len)?
+            }
+        };
+
+        let version = if packet.has_available(2) { Some(packet.g2()?) } else { None };
+        Ok(Container { compression, data, version })
+    }
+
+    /// Compresses and writes this container into a new packet, encrypting it with `key` if one
+    /// is given. The returned packet is positioned at the start of the container.
+    pub fn encode(&self, key: Option<[i32; 4]>) -> Result<Packet, PacketError> {
+        let compressed = self.compression.compress(&self.data)?;
+        let header_len = match self.compression {
+            Compression::None => 5,
+            _ => 9,
+        };
+        let trailer_len = if self.version.is_some() { 2 } else { 0 };
+
+        let mut packet = Packet::new(header_len + compressed.len() + trailer_len);
+        packet.p1(self.compression as u8);
+        packet.p4(compressed.len() as u32);
+        if self.compression != Compression::None {
+            packet.p4(self.data.len() as u32);
+        }
+        packet.write_all(&compressed)?;
+        if let Some(version) = self.version {
+            packet.p2(version);
+        }
+
+        if let Some(key) = key.filter(|key| *key != [0; 4]) {
+            packet.tiny_key_encrypt_range(&key, 5, header_len + compressed.len())?;
+        }
+        packet.set_pos(0)?;
+        Ok(packet)
+    }
+}
@@ -0,0 +1,117 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::io::Write;
+
+/// Splitting and joining of groups holding more than one file.
+///
+/// Multi-file groups store their files interleaved in one or more chunks, where every chunk
+/// holds a piece of each file in order. The group ends with a table of `g4s` sizes per chunk
+/// per file, each relative to the size of the previous file within the same chunk, followed by
+/// a single byte holding the chunk count. Groups holding a single file store it as-is.
+#[derive(Debug)]
+pub struct Group;
+
+impl Group {
+    /// Splits a group into the `file_count` files it contains.
+    pub fn split(data: &[u8], file_count: usize) -> Result<Vec<Vec<u8>>, PacketError> {
+        if file_count == 0 {
+            return Ok(Vec::new());
+        }
+        if file_count == 1 {
+            return Ok(vec![data.to_vec()]);
+        }
+        if data.is_empty() {
+            return error("Multi-file group is missing its chunk count.".to_string());
+        }
+
+        let mut table = Packet::from(data);
+        table.seek_from_end(1)?;
+        let chunks = table.g1()? as usize;
+        let table_len = chunks * file_count * 4;
+        if table_len + 1 > data.len() {
+            return error(format!(
+                "Group chunk table needs {} bytes but only {} are available.",
+                table_len + 1,
+                data.len()
+            ));
+        }
+
+        table.seek_from_end(1 + table_len)?;
+        let table_start = table.get_pos();
+        let mut chunk_sizes = vec![vec![0usize; file_count]; chunks];
+        let mut file_sizes = vec![0usize; file_count];
+        for sizes in chunk_sizes.iter_mut() {
+            let mut size = 0i32;
+            for (file, chunk_size) in sizes.iter_mut().enumerate() {
+                size = size.wrapping_add(table.g4s()?);
+                if size < 0 {
+                    return error(format!("Negative chunk size {} for file {}.", size, file));
+                }
+                *chunk_size = size as usize;
+                file_sizes[file] += size as usize;
+            }
+        }
+
+        if file_sizes.iter().sum::<usize>() != table_start {
+            return error(format!(
+                "Group chunk sizes sum to {} bytes but the group holds {}.",
+                file_sizes.iter().sum::<usize>(),
+                table_start
+            ));
+        }
+
+        let mut files: Vec<Vec<u8>> = file_sizes.iter().map(|size| Vec::with_capacity(*size)).collect();
+        let mut pos = 0;
+        for sizes in chunk_sizes {
+            for (file, size) in sizes.into_iter().enumerate() {
+                files[file].extend_from_slice(&data[pos..pos + size]);
+                pos += size;
+            }
+        }
+        Ok(files)
+    }
+
+    /// Joins `files` into a single group, spreading each file as evenly as possible across
+    /// `chunks` chunks. A single file is returned as-is.
+    pub fn join(files: &[Vec<u8>], chunks: u8) -> Result<Vec<u8>, PacketError> {
+        if files.len() == 1 {
+            return Ok(files[0].clone());
+        }
+        let chunks = chunks.max(1) as usize;
+
+        let chunk_sizes: Vec<Vec<usize>> = (0..chunks)
+            .map(|chunk| {
+                files
+                    .iter()
+                    .map(|file| {
+                        let piece = file.len().div_ceil(chunks);
+                        let start = (piece * chunk).min(file.len());
+                        (start + piece).min(file.len()) - start
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let data_len: usize = files.iter().map(Vec::len).sum();
+        let mut packet = Packet::new(data_len + chunks * files.len() * 4 + 1);
+        let mut offsets = vec![0usize; files.len()];
+        for sizes in &chunk_sizes {
+            for (file, size) in sizes.iter().enumerate() {
+                packet.write_all(&files[file][offsets[file]..offsets[file] + size])?;
+                offsets[file] += size;
+            }
+        }
+
+        for sizes in &chunk_sizes {
+            let mut previous = 0i32;
+            for size in sizes {
+                packet.p4s(*size as i32 - previous);
+                previous = *size as i32;
+            }
+        }
+        packet.p1(chunks as u8);
+
+        packet.set_pos(0)?;
+        Ok(packet.to_vec())
+    }
+}
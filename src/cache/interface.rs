@@ -0,0 +1,1049 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// The first byte of a component in the if3 format. If1 components start with their type,
+/// which never reaches this value.
+const IF3_MARKER: u8 = 255;
+
+/// The amount of sprite slots of an if1 inventory.
+pub const INVENTORY_SLOTS: usize = 20;
+
+/// The amount of ops of an if1 inventory.
+pub const INVENTORY_OPS: usize = 5;
+
+/// An interface, stored in the interfaces archive as a group holding one file per component.
+///
+/// Components refer to their parent by index within the same interface, forming a tree which
+/// can be walked with [Interface::tree].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interface {
+    pub id: u16,
+    /// The components of the interface, indexed by their file id.
+    pub components: Vec<Component>,
+}
+
+/// A component within an [Interface::tree], borrowing the component it wraps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentNode<'a> {
+    pub index: u16,
+    pub component: &'a Component,
+    pub children: Vec<ComponentNode<'a>>,
+}
+
+impl Interface {
+    /// Decodes an interface from the files of its group, as returned by
+    /// [Group::split](crate::cache::group::Group::split).
+    pub fn decode(id: u16, files: &[Vec<u8>]) -> Result<Interface, PacketError> {
+        let components = files
+            .iter()
+            .map(|file| Component::decode(&mut Packet::from(file.as_slice())))
+            .collect::<Result<_, _>>()?;
+        Ok(Interface { id, components })
+    }
+
+    /// Encodes every component into the files of the interface group.
+    pub fn encode(&self) -> Result<Vec<Vec<u8>>, PacketError> {
+        self.components
+            .iter()
+            .map(|component| {
+                let mut packet = Packet::empty();
+                component.encode(&mut packet)?;
+                packet.set_pos(0)?;
+                Ok(packet.to_vec())
+            })
+            .collect()
+    }
+
+    pub fn get(&self, index: u16) -> Option<&Component> {
+        self.components.get(index as usize)
+    }
+
+    pub fn get_mut(&mut self, index: u16) -> Option<&mut Component> {
+        self.components.get_mut(index as usize)
+    }
+
+    /// Returns the indices of the components without a parent.
+    pub fn roots(&self) -> impl Iterator<Item = u16> + '_ {
+        self.children_of(None)
+    }
+
+    /// Returns the indices of the direct children of the component at `index`.
+    pub fn children(&self, index: u16) -> impl Iterator<Item = u16> + '_ {
+        self.children_of(Some(index))
+    }
+
+    /// Builds the component tree, starting from the components without a parent. Components
+    /// whose parent is missing from the interface are left out.
+    pub fn tree(&self) -> Vec<ComponentNode<'_>> {
+        self.roots().map(|index| self.node(index)).collect()
+    }
+
+    fn node(&self, index: u16) -> ComponentNode<'_> {
+        ComponentNode {
+            index,
+            component: &self.components[index as usize],
+            children: self.children(index).map(|child| self.node(child)).collect(),
+        }
+    }
+
+    fn children_of(&self, parent: Option<u16>) -> impl Iterator<Item = u16> + '_ {
+        self.components
+            .iter()
+            .enumerate()
+            .filter(move |(index, component)| component.parent() == parent && Some(*index as u16) != parent)
+            .map(|(index, _)| index as u16)
+    }
+}
+
+/// A single component of an [Interface], in either of the two formats the client supports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Component {
+    If1(If1Component),
+    If3(If3Component),
+}
+
+impl Component {
+    /// Decodes a component, detecting its format from the leading [IF3_MARKER].
+    pub fn decode(packet: &mut Packet) -> Result<Component, PacketError> {
+        if packet.peek() == Some(IF3_MARKER) {
+            packet.skip(1);
+            Ok(Component::If3(If3Component::decode(packet)?))
+        } else {
+            Ok(Component::If1(If1Component::decode(packet)?))
+        }
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        match self {
+            Component::If1(component) => component.encode(packet),
+            Component::If3(component) => {
+                packet.p1(IF3_MARKER);
+                component.encode(packet)
+            }
+        }
+    }
+
+    /// The index of the parent component within the same interface.
+    pub fn parent(&self) -> Option<u16> {
+        match self {
+            Component::If1(component) => component.parent,
+            Component::If3(component) => component.parent,
+        }
+    }
+}
+
+/// The text settings shared by if1 text components.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStyle {
+    pub x_align: u8,
+    pub y_align: u8,
+    pub line_height: u8,
+    pub font: Option<u16>,
+    pub shadowed: bool,
+}
+
+impl TextStyle {
+    fn decode(packet: &mut Packet) -> Result<TextStyle, PacketError> {
+        Ok(TextStyle {
+            x_align: packet.g1()?,
+            y_align: packet.g1()?,
+            line_height: packet.g1()?,
+            font: g2_opt(packet)?,
+            shadowed: packet.g1()? == 1,
+        })
+    }
+
+    fn encode(&self, packet: &mut Packet) {
+        packet.p1(self.x_align);
+        packet.p1(self.y_align);
+        packet.p1(self.line_height);
+        p2_opt(packet, self.font);
+        packet.p1(self.shadowed as u8);
+    }
+}
+
+/// The colours of an if1 component, switching to the active colours while the scripts of the
+/// component are satisfied and to the hover colours while the mouse is over it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct If1Colours {
+    pub colour: u32,
+    pub active_colour: u32,
+    pub hover_colour: u32,
+    pub active_hover_colour: u32,
+}
+
+/// A sprite drawn over a slot of an if1 inventory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InventorySprite {
+    pub x: i16,
+    pub y: i16,
+    pub sprite: i32,
+}
+
+/// The contents of an if1 inventory component.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct If1Inventory {
+    pub draggable: bool,
+    pub interactable: bool,
+    pub usable: bool,
+    /// Inserts dragged objs rather than swapping them.
+    pub insert: bool,
+    pub pitch_x: u8,
+    pub pitch_y: u8,
+    /// The sprite drawn over each of the [INVENTORY_SLOTS] slots.
+    pub sprites: Vec<Option<InventorySprite>>,
+    /// The [INVENTORY_OPS] ops of the objs within the inventory.
+    pub ops: Vec<Option<String>>,
+}
+
+/// The type specific fields of an [If1Component], in the order of the type ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum If1Content {
+    /// `0`: a container of other components.
+    Layer { scroll_height: u16, hidden: bool },
+    /// `1`: never drawn by the client, but still carries its fields.
+    Unused { unknown0: u16, unknown1: u8, style: TextStyle, colour: u32 },
+    /// `2`: a grid of objs.
+    Inventory(If1Inventory),
+    /// `3`
+    Rectangle { filled: bool, colours: If1Colours },
+    /// `4`
+    Text { style: TextStyle, text: String, active_text: String, colours: If1Colours },
+    /// `5`
+    Graphic { sprite: i32, active_sprite: i32 },
+    /// `6`
+    Model {
+        model: Option<u16>,
+        active_model: Option<u16>,
+        anim: Option<u16>,
+        active_anim: Option<u16>,
+        zoom: u16,
+        xan: u16,
+        yan: u16,
+    },
+    /// `7`: a grid of objs drawn as their names.
+    InventoryText {
+        x_align: u8,
+        font: Option<u16>,
+        shadowed: bool,
+        colour: u32,
+        pitch_x: i16,
+        pitch_y: i16,
+        interactable: bool,
+        ops: Vec<Option<String>>,
+    },
+    /// `8`
+    Tooltip { text: String },
+}
+
+impl If1Content {
+    /// Returns the type id of the content.
+    pub fn id(&self) -> u8 {
+        match self {
+            If1Content::Layer { .. } => 0,
+            If1Content::Unused { .. } => 1,
+            If1Content::Inventory(_) => 2,
+            If1Content::Rectangle { .. } => 3,
+            If1Content::Text { .. } => 4,
+            If1Content::Graphic { .. } => 5,
+            If1Content::Model { .. } => 6,
+            If1Content::InventoryText { .. } => 7,
+            If1Content::Tooltip { .. } => 8,
+        }
+    }
+}
+
+/// The spell targeting of an if1 component, sent for inventories and target buttons.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct If1Target {
+    pub verb: String,
+    pub spell_name: String,
+    /// The kinds of entities the spell may target, as a 6-bit mask.
+    pub flags: u16,
+}
+
+/// A component in the legacy if1 format, driven by client scripts (cs1) rather than hooks.
+///
+/// - `g1` type, `g1` button type, `g2` content type
+/// - `g2s` x, `g2s` y, `g2` width, `g2` height, `g1` transparency
+/// - `g2` parent, `g2` hover redirect, where `65535` represents none
+/// - `g1` count, then `g1` comparator and `g2` operand per script
+/// - `g1` count, then `g2` length and `g2` opcodes per script
+/// - the fields of the [type](If1Content), the [target](If1Target) and the tooltip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct If1Component {
+    pub button_type: u8,
+    pub content_type: u16,
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+    pub transparency: u8,
+    pub parent: Option<u16>,
+    pub hover_redirect: Option<u16>,
+    /// The comparator and right hand side each script result is compared against.
+    pub script_comparisons: Vec<(u8, u16)>,
+    /// The cs1 scripts of the component as raw opcodes, where `65535` represents `-1`.
+    pub scripts: Vec<Vec<u16>>,
+    pub content: If1Content,
+    /// Only sent for inventories and components with button type `2`.
+    pub target: Option<If1Target>,
+    /// Only sent for components with button type `1`, `4`, `5` or `6`.
+    pub tooltip: Option<String>,
+}
+
+impl If1Component {
+    pub fn decode(packet: &mut Packet) -> Result<If1Component, PacketError> {
+        let kind = packet.g1()?;
+        let button_type = packet.g1()?;
+        let content_type = packet.g2()?;
+        let x = packet.g2s()?;
+        let y = packet.g2s()?;
+        let width = packet.g2()?;
+        let height = packet.g2()?;
+        let transparency = packet.g1()?;
+        let parent = g2_opt(packet)?;
+        let hover_redirect = g2_opt(packet)?;
+
+        let count = packet.g1()?;
+        let script_comparisons = (0..count).map(|_| Ok((packet.g1()?, packet.g2()?))).collect::<Result<_, PacketError>>()?;
+        let count = packet.g1()?;
+        let scripts = (0..count)
+            .map(|_| {
+                let len = packet.g2()?;
+                (0..len).map(|_| packet.g2()).collect::<Result<_, _>>()
+            })
+            .collect::<Result<_, PacketError>>()?;
+
+        let content = match kind {
+            0 => If1Content::Layer {
+                scroll_height: packet.g2()?,
+                hidden: packet.g1()? == 1,
+            },
+            1 => If1Content::Unused {
+                unknown0: packet.g2()?,
+                unknown1: packet.g1()?,
+                style: TextStyle::decode(packet)?,
+                colour: packet.g4()?,
+            },
+            2 => {
+                let draggable = packet.g1()? == 1;
+                let interactable = packet.g1()? == 1;
+                let usable = packet.g1()? == 1;
+                let insert = packet.g1()? == 1;
+                let pitch_x = packet.g1()?;
+                let pitch_y = packet.g1()?;
+                let sprites = (0..INVENTORY_SLOTS)
+                    .map(|_| match packet.g1()? {
+                        1 => Ok(Some(InventorySprite {
+                            x: packet.g2s()?,
+                            y: packet.g2s()?,
+                            sprite: packet.g4s()?,
+                        })),
+                        _ => Ok(None),
+                    })
+                    .collect::<Result<_, PacketError>>()?;
+                let ops = decode_ops(packet)?;
+                If1Content::Inventory(If1Inventory {
+                    draggable,
+                    interactable,
+                    usable,
+                    insert,
+                    pitch_x,
+                    pitch_y,
+                    sprites,
+                    ops,
+                })
+            }
+            3 => If1Content::Rectangle {
+                filled: packet.g1()? == 1,
+                colours: decode_colours(packet)?,
+            },
+            4 => If1Content::Text {
+                style: TextStyle::decode(packet)?,
+                text: packet.gjstr()?,
+                active_text: packet.gjstr()?,
+                colours: decode_colours(packet)?,
+            },
+            5 => If1Content::Graphic {
+                sprite: packet.g4s()?,
+                active_sprite: packet.g4s()?,
+            },
+            6 => If1Content::Model {
+                model: g2_opt(packet)?,
+                active_model: g2_opt(packet)?,
+                anim: g2_opt(packet)?,
+                active_anim: g2_opt(packet)?,
+                zoom: packet.g2()?,
+                xan: packet.g2()?,
+                yan: packet.g2()?,
+            },
+            7 => If1Content::InventoryText {
+                x_align: packet.g1()?,
+                font: g2_opt(packet)?,
+                shadowed: packet.g1()? == 1,
+                colour: packet.g4()?,
+                pitch_x: packet.g2s()?,
+                pitch_y: packet.g2s()?,
+                interactable: packet.g1()? == 1,
+                ops: decode_ops(packet)?,
+            },
+            8 => If1Content::Tooltip { text: packet.gjstr()? },
+            _ => return error(format!("Unknown if1 component type {}.", kind)),
+        };
+
+        let target = if Self::has_target(button_type, &content) {
+            Some(If1Target {
+                verb: packet.gjstr()?,
+                spell_name: packet.gjstr()?,
+                flags: packet.g2()?,
+            })
+        } else {
+            None
+        };
+        let tooltip = if Self::has_tooltip(button_type) {
+            Some(packet.gjstr()?)
+        } else {
+            None
+        };
+
+        Ok(If1Component {
+            button_type,
+            content_type,
+            x,
+            y,
+            width,
+            height,
+            transparency,
+            parent,
+            hover_redirect,
+            script_comparisons,
+            scripts,
+            content,
+            target,
+            tooltip,
+        })
+    }
+
+    /// Encodes the component. The target and tooltip are written as empty when required by the
+    /// button type but absent.
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if self.script_comparisons.len() > u8::MAX as usize || self.scripts.len() > u8::MAX as usize {
+            return error(format!(
+                "Too many if1 scripts ({} comparisons, {} scripts).",
+                self.script_comparisons.len(),
+                self.scripts.len()
+            ));
+        }
+        if let Some(script) = self.scripts.iter().find(|script| script.len() > u16::MAX as usize) {
+            return error(format!("If1 script of {} opcodes is too long.", script.len()));
+        }
+
+        packet.p1(self.content.id());
+        packet.p1(self.button_type);
+        packet.p2(self.content_type);
+        packet.p2s(self.x);
+        packet.p2s(self.y);
+        packet.p2(self.width);
+        packet.p2(self.height);
+        packet.p1(self.transparency);
+        p2_opt(packet, self.parent);
+        p2_opt(packet, self.hover_redirect);
+
+        packet.p1(self.script_comparisons.len() as u8);
+        for (comparator, operand) in &self.script_comparisons {
+            packet.p1(*comparator);
+            packet.p2(*operand);
+        }
+        packet.p1(self.scripts.len() as u8);
+        for script in &self.scripts {
+            packet.p2(script.len() as u16);
+            script.iter().for_each(|opcode| packet.p2(*opcode));
+        }
+
+        match &self.content {
+            If1Content::Layer { scroll_height, hidden } => {
+                packet.p2(*scroll_height);
+                packet.p1(*hidden as u8);
+            }
+            If1Content::Unused { unknown0, unknown1, style, colour } => {
+                packet.p2(*unknown0);
+                packet.p1(*unknown1);
+                style.encode(packet);
+                packet.p4(*colour);
+            }
+            If1Content::Inventory(inventory) => {
+                if inventory.sprites.len() != INVENTORY_SLOTS {
+                    return error(format!("Expected {} inventory sprites but found {}.", INVENTORY_SLOTS, inventory.sprites.len()));
+                }
+                packet.p1(inventory.draggable as u8);
+                packet.p1(inventory.interactable as u8);
+                packet.p1(inventory.usable as u8);
+                packet.p1(inventory.insert as u8);
+                packet.p1(inventory.pitch_x);
+                packet.p1(inventory.pitch_y);
+                for sprite in &inventory.sprites {
+                    match sprite {
+                        Some(sprite) => {
+                            packet.p1(1);
+                            packet.p2s(sprite.x);
+                            packet.p2s(sprite.y);
+                            packet.p4s(sprite.sprite);
+                        }
+                        None => packet.p1(0),
+                    }
+                }
+                encode_ops(packet, &inventory.ops)?;
+            }
+            If1Content::Rectangle { filled, colours } => {
+                packet.p1(*filled as u8);
+                encode_colours(packet, colours);
+            }
+            If1Content::Text { style, text, active_text, colours } => {
+                style.encode(packet);
+                packet.pjstr(text);
+                packet.pjstr(active_text);
+                encode_colours(packet, colours);
+            }
+            If1Content::Graphic { sprite, active_sprite } => {
+                packet.p4s(*sprite);
+                packet.p4s(*active_sprite);
+            }
+            If1Content::Model { model, active_model, anim, active_anim, zoom, xan, yan } => {
+                p2_opt(packet, *model);
+                p2_opt(packet, *active_model);
+                p2_opt(packet, *anim);
+                p2_opt(packet, *active_anim);
+                packet.p2(*zoom);
+                packet.p2(*xan);
+                packet.p2(*yan);
+            }
+            If1Content::InventoryText { x_align, font, shadowed, colour, pitch_x, pitch_y, interactable, ops } => {
+                packet.p1(*x_align);
+                p2_opt(packet, *font);
+                packet.p1(*shadowed as u8);
+                packet.p4(*colour);
+                packet.p2s(*pitch_x);
+                packet.p2s(*pitch_y);
+                packet.p1(*interactable as u8);
+                encode_ops(packet, ops)?;
+            }
+            If1Content::Tooltip { text } => packet.pjstr(text),
+        }
+
+        if Self::has_target(self.button_type, &self.content) {
+            let target = self.target.clone().unwrap_or_default();
+            packet.pjstr(&target.verb);
+            packet.pjstr(&target.spell_name);
+            packet.p2(target.flags);
+        }
+        if Self::has_tooltip(self.button_type) {
+            packet.pjstr(self.tooltip.as_deref().unwrap_or_default());
+        }
+        Ok(())
+    }
+
+    fn has_target(button_type: u8, content: &If1Content) -> bool {
+        button_type == 2 || matches!(content, If1Content::Inventory(_))
+    }
+
+    fn has_tooltip(button_type: u8) -> bool {
+        matches!(button_type, 1 | 4 | 5 | 6)
+    }
+}
+
+/// An argument of an if3 hook, the first of which is the id of the script to run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookArg {
+    Int(i32),
+    Str(String),
+}
+
+/// A hook of an if3 component: `g1` count, then `g1` is string and `g4` or `gjstr` value per
+/// argument. A count of `0` represents no hook.
+pub type Hook = Option<Vec<HookArg>>;
+
+/// The scripts an if3 component runs on events, followed by the vars, invs and stats whose
+/// transmits trigger the transmit hooks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct If3Hooks {
+    pub on_load: Hook,
+    pub on_mouse_over: Hook,
+    pub on_mouse_leave: Hook,
+    pub on_target_leave: Hook,
+    pub on_target_enter: Hook,
+    pub on_var_transmit: Hook,
+    pub on_inv_transmit: Hook,
+    pub on_stat_transmit: Hook,
+    pub on_timer: Hook,
+    pub on_op: Hook,
+    pub on_mouse_repeat: Hook,
+    pub on_click: Hook,
+    pub on_click_repeat: Hook,
+    pub on_release: Hook,
+    pub on_hold: Hook,
+    pub on_drag: Hook,
+    pub on_drag_complete: Hook,
+    pub on_scroll_wheel: Hook,
+    pub var_transmit_triggers: Option<Vec<i32>>,
+    pub inv_transmit_triggers: Option<Vec<i32>>,
+    pub stat_transmit_triggers: Option<Vec<i32>>,
+}
+
+impl If3Hooks {
+    fn decode(packet: &mut Packet) -> Result<If3Hooks, PacketError> {
+        Ok(If3Hooks {
+            on_load: decode_hook(packet)?,
+            on_mouse_over: decode_hook(packet)?,
+            on_mouse_leave: decode_hook(packet)?,
+            on_target_leave: decode_hook(packet)?,
+            on_target_enter: decode_hook(packet)?,
+            on_var_transmit: decode_hook(packet)?,
+            on_inv_transmit: decode_hook(packet)?,
+            on_stat_transmit: decode_hook(packet)?,
+            on_timer: decode_hook(packet)?,
+            on_op: decode_hook(packet)?,
+            on_mouse_repeat: decode_hook(packet)?,
+            on_click: decode_hook(packet)?,
+            on_click_repeat: decode_hook(packet)?,
+            on_release: decode_hook(packet)?,
+            on_hold: decode_hook(packet)?,
+            on_drag: decode_hook(packet)?,
+            on_drag_complete: decode_hook(packet)?,
+            on_scroll_wheel: decode_hook(packet)?,
+            var_transmit_triggers: decode_triggers(packet)?,
+            inv_transmit_triggers: decode_triggers(packet)?,
+            stat_transmit_triggers: decode_triggers(packet)?,
+        })
+    }
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        let hooks = [
+            &self.on_load,
+            &self.on_mouse_over,
+            &self.on_mouse_leave,
+            &self.on_target_leave,
+            &self.on_target_enter,
+            &self.on_var_transmit,
+            &self.on_inv_transmit,
+            &self.on_stat_transmit,
+            &self.on_timer,
+            &self.on_op,
+            &self.on_mouse_repeat,
+            &self.on_click,
+            &self.on_click_repeat,
+            &self.on_release,
+            &self.on_hold,
+            &self.on_drag,
+            &self.on_drag_complete,
+            &self.on_scroll_wheel,
+        ];
+        for hook in hooks {
+            encode_hook(packet, hook)?;
+        }
+        encode_triggers(packet, &self.var_transmit_triggers)?;
+        encode_triggers(packet, &self.inv_transmit_triggers)?;
+        encode_triggers(packet, &self.stat_transmit_triggers)
+    }
+}
+
+/// The type specific fields of an [If3Component].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum If3Content {
+    /// `0`: a container of other components.
+    Layer { scroll_width: u16, scroll_height: u16, no_click_through: bool },
+    /// `3`
+    Rectangle { colour: u32, filled: bool, transparency: u8 },
+    /// `4`
+    Text {
+        font: Option<u16>,
+        text: String,
+        line_height: u8,
+        x_align: u8,
+        y_align: u8,
+        shadowed: bool,
+        colour: u32,
+    },
+    /// `5`
+    Graphic {
+        sprite: i32,
+        angle: u16,
+        tiling: bool,
+        transparency: u8,
+        outline: u8,
+        shadow: u32,
+        flip_vertical: bool,
+        flip_horizontal: bool,
+    },
+    /// `6`
+    Model {
+        model: Option<u16>,
+        offset_x: i16,
+        offset_y: i16,
+        xan: u16,
+        yan: u16,
+        zan: u16,
+        zoom: u16,
+        anim: Option<u16>,
+        orthographic: bool,
+        unknown: u16,
+        /// Only sent when the width mode is not absolute.
+        model_width: u16,
+        /// Only sent when the height mode is not absolute.
+        model_height: u16,
+    },
+    /// `9`: a line from the top left to the bottom right of the component, where the height
+    /// of the component is signed.
+    Line { width: u8, colour: u32, mirrored: bool },
+}
+
+impl If3Content {
+    /// Returns the type id of the content.
+    pub fn id(&self) -> u8 {
+        match self {
+            If3Content::Layer { .. } => 0,
+            If3Content::Rectangle { .. } => 3,
+            If3Content::Text { .. } => 4,
+            If3Content::Graphic { .. } => 5,
+            If3Content::Model { .. } => 6,
+            If3Content::Line { .. } => 9,
+        }
+    }
+}
+
+/// A component in the if3 format, driven by client script hooks.
+///
+/// - [IF3_MARKER], `g1` type, `g2` content type
+/// - `g2s` x, `g2s` y, `g2` width, `g2` height
+/// - `g1s` width mode, `g1s` height mode, `g1s` x mode, `g1s` y mode
+/// - `g2` parent, where `65535` represents none, `g1` hidden
+/// - the fields of the [type](If3Content)
+/// - `g3` events, `gjstr` name, `g1` count then `gjstr` per op
+/// - `g1` drag dead zone, `g1` drag dead time, `g1` drag render behaviour, `gjstr` target verb
+/// - the [hooks](If3Hooks)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct If3Component {
+    pub content_type: u16,
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+    pub width_mode: i8,
+    pub height_mode: i8,
+    pub x_mode: i8,
+    pub y_mode: i8,
+    pub parent: Option<u16>,
+    pub hidden: bool,
+    pub content: If3Content,
+    /// The events the component responds to, as a 24-bit mask.
+    pub events: u32,
+    pub name: String,
+    pub ops: Vec<String>,
+    pub drag_dead_zone: u8,
+    pub drag_dead_time: u8,
+    pub drag_render_behaviour: bool,
+    pub target_verb: String,
+    pub hooks: Box<If3Hooks>,
+}
+
+impl If3Component {
+    /// Decodes the component, after the leading [IF3_MARKER].
+    pub fn decode(packet: &mut Packet) -> Result<If3Component, PacketError> {
+        let kind = packet.g1()?;
+        let content_type = packet.g2()?;
+        let x = packet.g2s()?;
+        let y = packet.g2s()?;
+        let width = packet.g2()?;
+        let height = packet.g2()?;
+        let width_mode = packet.g1s()?;
+        let height_mode = packet.g1s()?;
+        let x_mode = packet.g1s()?;
+        let y_mode = packet.g1s()?;
+        let parent = g2_opt(packet)?;
+        let hidden = packet.g1()? == 1;
+
+        let content = match kind {
+            0 => If3Content::Layer {
+                scroll_width: packet.g2()?,
+                scroll_height: packet.g2()?,
+                no_click_through: packet.g1()? == 1,
+            },
+            3 => If3Content::Rectangle {
+                colour: packet.g4()?,
+                filled: packet.g1()? == 1,
+                transparency: packet.g1()?,
+            },
+            4 => If3Content::Text {
+                font: g2_opt(packet)?,
+                text: packet.gjstr()?,
+                line_height: packet.g1()?,
+                x_align: packet.g1()?,
+                y_align: packet.g1()?,
+                shadowed: packet.g1()? == 1,
+                colour: packet.g4()?,
+            },
+            5 => If3Content::Graphic {
+                sprite: packet.g4s()?,
+                angle: packet.g2()?,
+                tiling: packet.g1()? == 1,
+                transparency: packet.g1()?,
+                outline: packet.g1()?,
+                shadow: packet.g4()?,
+                flip_vertical: packet.g1()? == 1,
+                flip_horizontal: packet.g1()? == 1,
+            },
+            6 => If3Content::Model {
+                model: g2_opt(packet)?,
+                offset_x: packet.g2s()?,
+                offset_y: packet.g2s()?,
+                xan: packet.g2()?,
+                yan: packet.g2()?,
+                zan: packet.g2()?,
+                zoom: packet.g2()?,
+                anim: g2_opt(packet)?,
+                orthographic: packet.g1()? == 1,
+                unknown: packet.g2()?,
+                model_width: if width_mode != 0 { packet.g2()? } else { 0 },
+                model_height: if height_mode != 0 { packet.g2()? } else { 0 },
+            },
+            9 => If3Content::Line {
+                width: packet.g1()?,
+                colour: packet.g4()?,
+                mirrored: packet.g1()? == 1,
+            },
+            _ => return error(format!("Unknown if3 component type {}.", kind)),
+        };
+
+        let events = packet.g3()? as u32;
+        let name = packet.gjstr()?;
+        let count = packet.g1()?;
+        let ops = (0..count).map(|_| packet.gjstr()).collect::<Result<_, _>>()?;
+        let drag_dead_zone = packet.g1()?;
+        let drag_dead_time = packet.g1()?;
+        let drag_render_behaviour = packet.g1()? == 1;
+        let target_verb = packet.gjstr()?;
+        let hooks = Box::new(If3Hooks::decode(packet)?);
+
+        Ok(If3Component {
+            content_type,
+            x,
+            y,
+            width,
+            height,
+            width_mode,
+            height_mode,
+            x_mode,
+            y_mode,
+            parent,
+            hidden,
+            content,
+            events,
+            name,
+            ops,
+            drag_dead_zone,
+            drag_dead_time,
+            drag_render_behaviour,
+            target_verb,
+            hooks,
+        })
+    }
+
+    /// Encodes the component, excluding the leading [IF3_MARKER].
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if self.events > 0xFFFFFF {
+            return error(format!("If3 events {:#x} do not fit in 24 bits.", self.events));
+        }
+        if self.ops.len() > u8::MAX as usize {
+            return error(format!("Too many if3 ops ({}).", self.ops.len()));
+        }
+
+        packet.p1(self.content.id());
+        packet.p2(self.content_type);
+        packet.p2s(self.x);
+        packet.p2s(self.y);
+        packet.p2(self.width);
+        packet.p2(self.height);
+        packet.p1s(self.width_mode);
+        packet.p1s(self.height_mode);
+        packet.p1s(self.x_mode);
+        packet.p1s(self.y_mode);
+        p2_opt(packet, self.parent);
+        packet.p1(self.hidden as u8);
+
+        match &self.content {
+            If3Content::Layer { scroll_width, scroll_height, no_click_through } => {
+                packet.p2(*scroll_width);
+                packet.p2(*scroll_height);
+                packet.p1(*no_click_through as u8);
+            }
+            If3Content::Rectangle { colour, filled, transparency } => {
+                packet.p4(*colour);
+                packet.p1(*filled as u8);
+                packet.p1(*transparency);
+            }
+            If3Content::Text { font, text, line_height, x_align, y_align, shadowed, colour } => {
+                p2_opt(packet, *font);
+                packet.pjstr(text);
+                packet.p1(*line_height);
+                packet.p1(*x_align);
+                packet.p1(*y_align);
+                packet.p1(*shadowed as u8);
+                packet.p4(*colour);
+            }
+            If3Content::Graphic { sprite, angle, tiling, transparency, outline, shadow, flip_vertical, flip_horizontal } => {
+                packet.p4s(*sprite);
+                packet.p2(*angle);
+                packet.p1(*tiling as u8);
+                packet.p1(*transparency);
+                packet.p1(*outline);
+                packet.p4(*shadow);
+                packet.p1(*flip_vertical as u8);
+                packet.p1(*flip_horizontal as u8);
+            }
+            If3Content::Model {
+                model,
+                offset_x,
+                offset_y,
+                xan,
+                yan,
+                zan,
+                zoom,
+                anim,
+                orthographic,
+                unknown,
+                model_width,
+                model_height,
+            } => {
+                p2_opt(packet, *model);
+                packet.p2s(*offset_x);
+                packet.p2s(*offset_y);
+                packet.p2(*xan);
+                packet.p2(*yan);
+                packet.p2(*zan);
+                packet.p2(*zoom);
+                p2_opt(packet, *anim);
+                packet.p1(*orthographic as u8);
+                packet.p2(*unknown);
+                if self.width_mode != 0 {
+                    packet.p2(*model_width);
+                }
+                if self.height_mode != 0 {
+                    packet.p2(*model_height);
+                }
+            }
+            If3Content::Line { width, colour, mirrored } => {
+                packet.p1(*width);
+                packet.p4(*colour);
+                packet.p1(*mirrored as u8);
+            }
+        }
+
+        packet.p3(self.events);
+        packet.pjstr(&self.name);
+        packet.p1(self.ops.len() as u8);
+        self.ops.iter().for_each(|op| packet.pjstr(op));
+        packet.p1(self.drag_dead_zone);
+        packet.p1(self.drag_dead_time);
+        packet.p1(self.drag_render_behaviour as u8);
+        packet.pjstr(&self.target_verb);
+        self.hooks.encode(packet)
+    }
+}
+
+/// Reads a `g2` where `65535` represents none.
+fn g2_opt(packet: &mut Packet) -> Result<Option<u16>, PacketError> {
+    let value = packet.g2()?;
+    Ok((value != u16::MAX).then_some(value))
+}
+
+fn p2_opt(packet: &mut Packet, value: Option<u16>) {
+    packet.p2(value.unwrap_or(u16::MAX));
+}
+
+fn decode_colours(packet: &mut Packet) -> Result<If1Colours, PacketError> {
+    Ok(If1Colours {
+        colour: packet.g4()?,
+        active_colour: packet.g4()?,
+        hover_colour: packet.g4()?,
+        active_hover_colour: packet.g4()?,
+    })
+}
+
+fn encode_colours(packet: &mut Packet, colours: &If1Colours) {
+    packet.p4(colours.colour);
+    packet.p4(colours.active_colour);
+    packet.p4(colours.hover_colour);
+    packet.p4(colours.active_hover_colour);
+}
+
+/// Reads the [INVENTORY_OPS] ops of an if1 inventory, where empty ops are absent.
+fn decode_ops(packet: &mut Packet) -> Result<Vec<Option<String>>, PacketError> {
+    (0..INVENTORY_OPS)
+        .map(|_| {
+            let op = packet.gjstr()?;
+            Ok((!op.is_empty()).then_some(op))
+        })
+        .collect()
+}
+
+fn encode_ops(packet: &mut Packet, ops: &[Option<String>]) -> Result<(), PacketError> {
+    if ops.len() != INVENTORY_OPS {
+        return error(format!("Expected {} inventory ops but found {}.", INVENTORY_OPS, ops.len()));
+    }
+    ops.iter().for_each(|op| packet.pjstr(op.as_deref().unwrap_or_default()));
+    Ok(())
+}
+
+fn decode_hook(packet: &mut Packet) -> Result<Hook, PacketError> {
+    let count = packet.g1()?;
+    if count == 0 {
+        return Ok(None);
+    }
+    let args = (0..count)
+        .map(|_| match packet.g1()? {
+            0 => Ok(HookArg::Int(packet.g4s()?)),
+            1 => Ok(HookArg::Str(packet.gjstr()?)),
+            kind => error(format!("Unknown hook argument type {}.", kind)),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Some(args))
+}
+
+fn encode_hook(packet: &mut Packet, hook: &Hook) -> Result<(), PacketError> {
+    let args = hook.as_deref().unwrap_or_default();
+    if args.len() > u8::MAX as usize {
+        return error(format!("Too many hook arguments ({}).", args.len()));
+    }
+    packet.p1(args.len() as u8);
+    for arg in args {
+        match arg {
+            HookArg::Int(value) => {
+                packet.p1(0);
+                packet.p4s(*value);
+            }
+            HookArg::Str(value) => {
+                packet.p1(1);
+                packet.pjstr(value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode_triggers(packet: &mut Packet) -> Result<Option<Vec<i32>>, PacketError> {
+    let count = packet.g1()?;
+    if count == 0 {
+        return Ok(None);
+    }
+    (0..count).map(|_| packet.g4s()).collect::<Result<_, _>>().map(Some)
+}
+
+fn encode_triggers(packet: &mut Packet, triggers: &Option<Vec<i32>>) -> Result<(), PacketError> {
+    let triggers = triggers.as_deref().unwrap_or_default();
+    if triggers.len() > u8::MAX as usize {
+        return error(format!("Too many transmit triggers ({}).", triggers.len()));
+    }
+    packet.p1(triggers.len() as u8);
+    triggers.iter().for_each(|trigger| packet.p4s(*trigger));
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use crate::cache::container::Compression;
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::io::Write;
+
+/// A single named entry within a [JagArchive].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JagEntry {
+    pub name_hash: i32,
+    pub data: Vec<u8>,
+}
+
+/// A legacy `.jag` archive as used by 317-era caches for the title screen, configs and media.
+///
+/// The archive begins with two `g3` lengths, the decompressed and compressed length. If they
+/// differ, the remainder of the archive is bzip2 compressed as a whole, otherwise each entry is
+/// compressed individually. The (decompressed) body holds a `g2` entry count, a table of name
+/// hash, decompressed and compressed `g3` sizes per entry, followed by the entry data.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JagArchive {
+    pub entries: Vec<JagEntry>,
+    /// Whether the archive is compressed as a whole rather than per entry.
+    pub compressed_whole: bool,
+}
+
+impl JagArchive {
+    /// Hashes an entry name the same way the client does, ignoring case.
+    pub fn name_hash(name: &str) -> i32 {
+        name.to_uppercase()
+            .chars()
+            .fold(0i32, |hash, c| hash.wrapping_mul(61).wrapping_add(c as i32 - 32))
+    }
+
+    /// Decodes an archive starting at the packet's current position.
+    pub fn decode(packet: &mut Packet) -> Result<JagArchive, PacketError> {
+        let len = packet.g3()?;
+        let compressed_len = packet.g3()?;
+        if !packet.has_available(compressed_len) {
+            return error(format!(
+                "Not enough data for jag archive. Needed {}, have {}.",
+                compressed_len,
+                packet.available_count()
+            ));
+        }
+
+        let compressed_whole = len != compressed_len;
+        let body = packet.gdata(compressed_len);
+        let mut body = if compressed_whole {
+            Packet::from(Compression::Bzip2.decompress(&body, len)?)
+        } else {
+            Packet::from(body)
+        };
+
+        let count = body.g2()? as usize;
+        let mut table = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name_hash = body.g4s()?;
+            let len = body.g3()?;
+            let compressed_len = body.g3()?;
+            table.push((name_hash, len, compressed_len));
+        }
+
+        let mut entries = Vec::with_capacity(count);
+        for (name_hash, len, compressed_len) in table {
+            if !body.has_available(compressed_len) {
+                return error(format!("Not enough data for jag entry {}.", name_hash));
+            }
+            let data = body.gdata(compressed_len);
+            let data = if compressed_whole { data } else { Compression::Bzip2.decompress(&data, len)? };
+            entries.push(JagEntry { name_hash, data });
+        }
+
+        Ok(JagArchive { entries, compressed_whole })
+    }
+
+    /// Encodes this archive into a new packet positioned at the start of the archive.
+    pub fn encode(&self) -> Result<Packet, PacketError> {
+        let mut body = Packet::empty();
+        let mut data = Vec::with_capacity(self.entries.len());
+        body.p2(self.entries.len() as u16);
+        for entry in &self.entries {
+            let stored = if self.compressed_whole {
+                entry.data.clone()
+            } else {
+                Compression::Bzip2.compress(&entry.data)?
+            };
+            body.p4s(entry.name_hash);
+            body.p3(entry.data.len() as u32);
+            body.p3(stored.len() as u32);
+            data.push(stored);
+        }
+        for stored in &data {
+            body.write_all(stored)?;
+        }
+        body.set_pos(0)?;
+
+        let len = body.available_count();
+        let body = if self.compressed_whole {
+            Compression::Bzip2.compress(body.as_ref())?
+        } else {
+            body.to_vec()
+        };
+
+        let mut packet = Packet::new(6 + body.len());
+        packet.p3(len as u32);
+        packet.p3(body.len() as u32);
+        packet.write_all(&body)?;
+        packet.set_pos(0)?;
+        Ok(packet)
+    }
+
+    /// Returns the data of the entry with the given name.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        let name_hash = Self::name_hash(name);
+        self.entries
+            .iter()
+            .find(|entry| entry.name_hash == name_hash)
+            .map(|entry| entry.data.as_slice())
+    }
+
+    /// Replaces the data of the entry with the given name, appending a new entry if no entry
+    /// with that name exists.
+    pub fn insert(&mut self, name: &str, data: Vec<u8>) {
+        let name_hash = Self::name_hash(name);
+        match self.entries.iter_mut().find(|entry| entry.name_hash == name_hash) {
+            Some(entry) => entry.data = data,
+            None => self.entries.push(JagEntry { name_hash, data }),
+        }
+    }
+
+    /// Removes the entry with the given name, returning its data if it existed.
+    pub fn remove(&mut self, name: &str) -> Option<Vec<u8>> {
+        let name_hash = Self::name_hash(name);
+        let position = self.entries.iter().position(|entry| entry.name_hash == name_hash)?;
+        Some(self.entries.remove(position).data)
+    }
+}
@@ -0,0 +1,260 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// The width and length of a region in tiles.
+pub const MAP_SIZE: usize = 64;
+
+/// The amount of planes in a region.
+pub const MAP_PLANES: usize = 4;
+
+/// The largest value of a single smart within an extended smart.
+const EXTENDED_SMART_MAX: usize = 32767;
+
+/// The encoding of the map files, which widened in later revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapFormat {
+    /// `g1` tile opcodes and overlay ids, and `gsmart` loc id offsets.
+    Legacy,
+    /// `g2` tile opcodes and overlay ids, and extended smart loc id offsets, where a smart of
+    /// 32767 is followed by another smart to be added to it.
+    Extended,
+}
+
+/// A single tile within a [MapTiles].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tile {
+    /// The height of the tile, or `None` if the client calculates it procedurally.
+    pub height: Option<u8>,
+    pub overlay: u16,
+    pub shape: u8,
+    pub rotation: u8,
+    pub settings: u8,
+    pub underlay: u16,
+}
+
+/// The terrain of a region, stored in the `m{x}_{y}` group of the maps archive.
+///
+/// Every tile is read in order of plane, x and then y as a sequence of opcodes:
+///
+/// - `0`: the height is procedural and the tile ends
+/// - `1`: `g1` height, and the tile ends
+/// - `2..=49`: the overlay shape and rotation, followed by the overlay id
+/// - `50..=81`: the settings
+/// - `82..`: the underlay id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapTiles {
+    tiles: Vec<Tile>,
+}
+
+impl Default for MapTiles {
+    fn default() -> Self {
+        Self {
+            tiles: vec![Tile::default(); MAP_PLANES * MAP_SIZE * MAP_SIZE],
+        }
+    }
+}
+
+impl MapTiles {
+    pub fn get(&self, plane: usize, x: usize, y: usize) -> Option<&Tile> {
+        self.tiles.get(Self::index(plane, x, y)?)
+    }
+
+    pub fn get_mut(&mut self, plane: usize, x: usize, y: usize) -> Option<&mut Tile> {
+        self.tiles.get_mut(Self::index(plane, x, y)?)
+    }
+
+    pub fn decode(packet: &mut Packet, format: MapFormat) -> Result<MapTiles, PacketError> {
+        let mut tiles = MapTiles::default();
+        for tile in tiles.tiles.iter_mut() {
+            loop {
+                let opcode = match format {
+                    MapFormat::Legacy => packet.g1()? as u16,
+                    MapFormat::Extended => packet.g2()?,
+                };
+                match opcode {
+                    0 => break,
+                    1 => {
+                        tile.height = Some(packet.g1()?);
+                        break;
+                    }
+                    2..=49 => {
+                        tile.shape = ((opcode - 2) / 4) as u8;
+                        tile.rotation = ((opcode - 2) & 3) as u8;
+                        tile.overlay = match format {
+                            MapFormat::Legacy => packet.g1()? as u16,
+                            MapFormat::Extended => packet.g2()?,
+                        };
+                    }
+                    50..=81 => tile.settings = (opcode - 49) as u8,
+                    _ => tile.underlay = opcode - 81,
+                }
+            }
+        }
+        Ok(tiles)
+    }
+
+    pub fn encode(&self, packet: &mut Packet, format: MapFormat) -> Result<(), PacketError> {
+        let max_opcode = match format {
+            MapFormat::Legacy => u8::MAX as u16,
+            MapFormat::Extended => u16::MAX,
+        };
+        let put = |packet: &mut Packet, value: u16| match format {
+            MapFormat::Legacy => packet.p1(value as u8),
+            MapFormat::Extended => packet.p2(value),
+        };
+
+        for tile in &self.tiles {
+            if tile.overlay != 0 || tile.shape != 0 || tile.rotation != 0 {
+                if tile.shape > 11 || tile.rotation > 3 || tile.overlay > max_opcode {
+                    return error(format!("Invalid overlay {} on tile {:?}.", tile.overlay, tile));
+                }
+                put(packet, 2 + tile.shape as u16 * 4 + tile.rotation as u16);
+                put(packet, tile.overlay);
+            }
+            if tile.settings != 0 {
+                if tile.settings > 32 {
+                    return error(format!("Invalid settings {} on tile {:?}.", tile.settings, tile));
+                }
+                put(packet, 49 + tile.settings as u16);
+            }
+            if tile.underlay != 0 {
+                if tile.underlay > max_opcode - 81 {
+                    return error(format!("Invalid underlay {} on tile {:?}.", tile.underlay, tile));
+                }
+                put(packet, 81 + tile.underlay);
+            }
+            match tile.height {
+                Some(height) => {
+                    put(packet, 1);
+                    packet.p1(height);
+                }
+                None => put(packet, 0),
+            }
+        }
+        Ok(())
+    }
+
+    fn index(plane: usize, x: usize, y: usize) -> Option<usize> {
+        if plane >= MAP_PLANES || x >= MAP_SIZE || y >= MAP_SIZE {
+            return None;
+        }
+        Some(plane * MAP_SIZE * MAP_SIZE + x * MAP_SIZE + y)
+    }
+}
+
+/// A single object placed within a [MapLocs].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Loc {
+    pub id: u32,
+    pub plane: u8,
+    pub x: u8,
+    pub y: u8,
+    pub shape: u8,
+    pub rotation: u8,
+}
+
+impl Loc {
+    /// Returns the position packed as sent in the file: `plane << 12 | x << 6 | y`.
+    fn packed_pos(&self) -> usize {
+        (self.plane as usize) << 12 | (self.x as usize) << 6 | self.y as usize
+    }
+}
+
+/// The objects placed within a region, stored in the `l{x}_{y}` group of the maps archive and
+/// usually encrypted with the region's XTEA key.
+///
+/// Locs are grouped by id in ascending order, each group beginning with the offset from the
+/// previous id and ending with a zero. Within a group, each loc holds the offset from the
+/// previous packed position plus one, followed by `g1` holding `shape << 2 | rotation`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MapLocs {
+    pub locs: Vec<Loc>,
+}
+
+impl MapLocs {
+    pub fn decode(packet: &mut Packet, format: MapFormat) -> Result<MapLocs, PacketError> {
+        let mut locs = Vec::new();
+        let mut id = -1i64;
+        loop {
+            let id_offset = match format {
+                MapFormat::Legacy => packet.gsmart_u16()?,
+                MapFormat::Extended => gextended_smart(packet)?,
+            };
+            if id_offset == 0 {
+                break;
+            }
+            id += id_offset as i64;
+
+            let mut pos = 0;
+            loop {
+                let pos_offset = packet.gsmart_u16()?;
+                if pos_offset == 0 {
+                    break;
+                }
+                pos += pos_offset - 1;
+
+                let attributes = packet.g1()?;
+                locs.push(Loc {
+                    id: id as u32,
+                    plane: (pos >> 12 & 0x3) as u8,
+                    x: (pos >> 6 & 0x3F) as u8,
+                    y: (pos & 0x3F) as u8,
+                    shape: attributes >> 2,
+                    rotation: attributes & 0x3,
+                });
+            }
+        }
+        Ok(MapLocs { locs })
+    }
+
+    /// Writes the locs, sorted by id and then position as the format requires.
+    pub fn encode(&self, packet: &mut Packet, format: MapFormat) -> Result<(), PacketError> {
+        let mut locs = self.locs.clone();
+        locs.sort_by_key(|loc| (loc.id, loc.packed_pos()));
+
+        let mut previous_id = -1i64;
+        for group in locs.chunk_by(|a, b| a.id == b.id) {
+            let id_offset = (group[0].id as i64 - previous_id) as usize;
+            match format {
+                MapFormat::Legacy if id_offset > EXTENDED_SMART_MAX => {
+                    return error(format!("Loc id {} is too far from the previous id.", group[0].id));
+                }
+                MapFormat::Legacy => packet.psmart_u16(id_offset),
+                MapFormat::Extended => pextended_smart(packet, id_offset),
+            }
+            previous_id = group[0].id as i64;
+
+            let mut previous_pos = 0;
+            for loc in group {
+                if loc.plane as usize >= MAP_PLANES || loc.x as usize >= MAP_SIZE || loc.y as usize >= MAP_SIZE {
+                    return error(format!("Loc {:?} lies outside of the region.", loc));
+                }
+                packet.psmart_u16(loc.packed_pos() - previous_pos + 1);
+                packet.p1(loc.shape << 2 | loc.rotation & 0x3);
+                previous_pos = loc.packed_pos();
+            }
+            packet.psmart_u16(0);
+        }
+        packet.psmart_u16(0);
+        Ok(())
+    }
+}
+
+fn gextended_smart(packet: &mut Packet) -> Result<usize, PacketError> {
+    let mut value = 0;
+    loop {
+        let next = packet.gsmart_u16()?;
+        value += next;
+        if next != EXTENDED_SMART_MAX {
+            return Ok(value);
+        }
+    }
+}
+
+fn pextended_smart(packet: &mut Packet, mut value: usize) {
+    while value >= EXTENDED_SMART_MAX {
+        packet.psmart_u16(EXTENDED_SMART_MAX);
+        value -= EXTENDED_SMART_MAX;
+    }
+    packet.psmart_u16(value);
+}
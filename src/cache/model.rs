@@ -0,0 +1,451 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::io::Write;
+
+/// The trailer of a model in the [new](ModelFormat::New) format.
+const NEW_FORMAT_MARKER: [u8; 2] = [0xFF, 0xFF];
+
+/// The size of the legacy header at the end of the model.
+const LEGACY_HEADER_SIZE: usize = 18;
+
+/// The size of the new header at the end of the model, including the [NEW_FORMAT_MARKER].
+const NEW_HEADER_SIZE: usize = 23;
+
+/// The model priority signalling that every face carries its own priority.
+const FACE_PRIORITIES: u8 = 255;
+
+/// The range of deltas a signed smart can hold.
+const DELTA_RANGE: std::ops::RangeInclusive<i32> = -16384..=16383;
+
+/// The layout of a model, detected from its trailer by [ModelFormat::detect].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModelFormat {
+    /// Textures are signalled through the face types, reusing the colour of the face as the
+    /// texture id.
+    #[default]
+    Legacy,
+    /// Textures and texture coordinates are stored per face, and the model ends with
+    /// [NEW_FORMAT_MARKER].
+    New,
+}
+
+impl ModelFormat {
+    pub fn detect(data: &[u8]) -> ModelFormat {
+        if data.ends_with(&NEW_FORMAT_MARKER) {
+            ModelFormat::New
+        } else {
+            ModelFormat::Legacy
+        }
+    }
+
+    fn header_size(self) -> usize {
+        match self {
+            ModelFormat::Legacy => LEGACY_HEADER_SIZE,
+            ModelFormat::New => NEW_HEADER_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Vertex {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// A triangle between three vertices, coloured with a 16-bit HSL colour.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Face {
+    pub a: u16,
+    pub b: u16,
+    pub c: u16,
+    pub colour: u16,
+}
+
+/// The three vertices a texture is mapped across.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextureFace {
+    pub p: u16,
+    pub m: u16,
+    pub n: u16,
+}
+
+/// A model, stored in the models archive as a series of sections followed by a header at the
+/// end of the file describing the size of each section.
+///
+/// Vertices are stored as a flag byte per vertex followed by `gsmart_s16` deltas for each axis
+/// whose flag bit is set, and faces are stored as a compression type per face followed by
+/// `gsmart_s16` vertex index deltas, where:
+///
+/// - `1`: all three indices are sent
+/// - `2`: the face shares the first and last vertex of the previous face
+/// - `3`: the face shares the last and second vertex of the previous face
+/// - `4`: the face shares the second and first vertex of the previous face
+///
+/// The optional per vertex and per face sections are present when the matching field is
+/// `Some`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Model {
+    pub format: ModelFormat,
+    pub vertices: Vec<Vertex>,
+    pub faces: Vec<Face>,
+    pub texture_faces: Vec<TextureFace>,
+    /// The label of each vertex, grouping vertices transformed together by animations.
+    pub vertex_labels: Option<Vec<u8>>,
+    /// The render type of each face. In the legacy format, bit `1` marks the face as textured
+    /// with its colour as the texture id, and the bits above hold its texture face.
+    pub face_types: Option<Vec<u8>>,
+    /// The priority of every face, ignored when [face_priorities](Model::face_priorities) are
+    /// present.
+    pub priority: u8,
+    pub face_priorities: Option<Vec<u8>>,
+    pub face_alphas: Option<Vec<u8>>,
+    /// The label of each face, grouping faces whose alpha is transformed by animations.
+    pub face_labels: Option<Vec<u8>>,
+    /// The texture of each face, only present in the new format.
+    pub face_textures: Option<Vec<Option<u16>>>,
+    /// The texture face of each textured face, only present in the new format when the model
+    /// has texture faces.
+    pub face_texture_coords: Option<Vec<Option<u8>>>,
+}
+
+impl Model {
+    pub fn decode(packet: &mut Packet) -> Result<Model, PacketError> {
+        let data = packet.slice_remaining().to_vec();
+        packet.skip(data.len());
+
+        let format = ModelFormat::detect(&data);
+        if data.len() < format.header_size() {
+            return error(format!("Model of {} bytes is too short.", data.len()));
+        }
+        let body_len = data.len() - format.header_size();
+        let mut header = Packet::from(&data[body_len..]);
+        let vertex_count = header.g2()? as usize;
+        let face_count = header.g2()? as usize;
+        let texture_count = header.g1()? as usize;
+        let has_face_types = header.g1()? == 1;
+        let priority = header.g1()?;
+        let has_face_alphas = header.g1()? == 1;
+        let has_face_labels = header.g1()? == 1;
+        let has_face_textures = format == ModelFormat::New && header.g1()? == 1;
+        let has_vertex_labels = header.g1()? == 1;
+        let x_len = header.g2()? as usize;
+        let y_len = header.g2()? as usize;
+        let z_len = header.g2()? as usize;
+        let index_len = header.g2()? as usize;
+        let coord_len = if format == ModelFormat::New { header.g2()? as usize } else { 0 };
+
+        let mut offset = 0;
+        let mut take = |present: bool, len: usize| -> Result<Option<&[u8]>, PacketError> {
+            if !present {
+                return Ok(None);
+            }
+            if offset + len > body_len {
+                return error(format!("Model section of {} bytes at {} exceeds the model.", len, offset));
+            }
+            offset += len;
+            Ok(Some(&data[offset - len..offset]))
+        };
+
+        let new = format == ModelFormat::New;
+        let mapping_types = take(new, texture_count)?;
+        let vertex_flags = take(true, vertex_count)?.unwrap_or_default();
+        let new_face_types = take(new && has_face_types, face_count)?;
+        let compression_types = take(true, face_count)?.unwrap_or_default();
+        let face_priorities = take(priority == FACE_PRIORITIES, face_count)?;
+        let face_labels = take(has_face_labels, face_count)?;
+        let legacy_face_types = take(!new && has_face_types, face_count)?;
+        let vertex_labels = take(has_vertex_labels, vertex_count)?;
+        let face_alphas = take(has_face_alphas, face_count)?;
+        let indices = take(true, index_len)?.unwrap_or_default();
+        let face_textures = take(has_face_textures, face_count * 2)?;
+        let coords = take(new, coord_len)?;
+        let colours = take(true, face_count * 2)?.unwrap_or_default();
+        let texture_faces = take(true, texture_count * 6)?.unwrap_or_default();
+        let xs = take(true, x_len)?.unwrap_or_default();
+        let ys = take(true, y_len)?.unwrap_or_default();
+        let zs = take(true, z_len)?.unwrap_or_default();
+
+        if let Some(kind) = mapping_types.unwrap_or_default().iter().find(|kind| **kind != 0) {
+            return error(format!("Unsupported texture mapping type {}.", kind));
+        }
+
+        let (mut xs, mut ys, mut zs) = (Packet::from(xs), Packet::from(ys), Packet::from(zs));
+        let mut vertex = Vertex::default();
+        let mut vertices = Vec::with_capacity(vertex_count);
+        for flags in vertex_flags {
+            if flags & 1 != 0 {
+                vertex.x += xs.gsmart_s16()?;
+            }
+            if flags & 2 != 0 {
+                vertex.y += ys.gsmart_s16()?;
+            }
+            if flags & 4 != 0 {
+                vertex.z += zs.gsmart_s16()?;
+            }
+            vertices.push(vertex);
+        }
+
+        let (mut indices, mut colours) = (Packet::from(indices), Packet::from(colours));
+        let (mut a, mut b, mut c, mut last) = (0i32, 0i32, 0i32, 0i32);
+        let mut faces = Vec::with_capacity(face_count);
+        for compression in compression_types {
+            match compression {
+                1 => {
+                    a = indices.gsmart_s16()? + last;
+                    b = indices.gsmart_s16()? + a;
+                    c = indices.gsmart_s16()? + b;
+                }
+                2 => {
+                    b = c;
+                    c = indices.gsmart_s16()? + last;
+                }
+                3 => {
+                    a = c;
+                    c = indices.gsmart_s16()? + last;
+                }
+                4 => {
+                    std::mem::swap(&mut a, &mut b);
+                    c = indices.gsmart_s16()? + last;
+                }
+                _ => return error(format!("Unknown face compression type {}.", compression)),
+            }
+            last = c;
+            faces.push(Face {
+                a: a as u16,
+                b: b as u16,
+                c: c as u16,
+                colour: colours.g2()?,
+            });
+        }
+
+        let face_textures = match face_textures {
+            Some(textures) => {
+                let mut textures = Packet::from(textures);
+                Some((0..face_count).map(|_| Ok(textures.g2()?.checked_sub(1))).collect::<Result<Vec<_>, PacketError>>()?)
+            }
+            None => None,
+        };
+        let face_texture_coords = match (&face_textures, coords) {
+            (Some(textures), Some(coords)) if texture_count > 0 => {
+                let mut coords = Packet::from(coords);
+                let coords = textures
+                    .iter()
+                    .map(|texture| match texture {
+                        Some(_) => Ok(coords.g1()?.checked_sub(1)),
+                        None => Ok(None),
+                    })
+                    .collect::<Result<_, PacketError>>()?;
+                Some(coords)
+            }
+            _ => None,
+        };
+
+        let mut texture_faces = Packet::from(texture_faces);
+        let texture_faces = (0..texture_count)
+            .map(|_| {
+                Ok(TextureFace {
+                    p: texture_faces.g2()?,
+                    m: texture_faces.g2()?,
+                    n: texture_faces.g2()?,
+                })
+            })
+            .collect::<Result<_, PacketError>>()?;
+
+        Ok(Model {
+            format,
+            vertices,
+            faces,
+            texture_faces,
+            vertex_labels: vertex_labels.map(<[u8]>::to_vec),
+            face_types: new_face_types.or(legacy_face_types).map(<[u8]>::to_vec),
+            priority: if face_priorities.is_some() { 0 } else { priority },
+            face_priorities: face_priorities.map(<[u8]>::to_vec),
+            face_alphas: face_alphas.map(<[u8]>::to_vec),
+            face_labels: face_labels.map(<[u8]>::to_vec),
+            face_textures,
+            face_texture_coords,
+        })
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        self.validate()?;
+        let new = self.format == ModelFormat::New;
+
+        let mut vertex_flags = Packet::empty();
+        let (mut xs, mut ys, mut zs) = (Packet::empty(), Packet::empty(), Packet::empty());
+        let mut previous = Vertex::default();
+        for vertex in &self.vertices {
+            let (dx, dy, dz) = (vertex.x - previous.x, vertex.y - previous.y, vertex.z - previous.z);
+            if !DELTA_RANGE.contains(&dx) || !DELTA_RANGE.contains(&dy) || !DELTA_RANGE.contains(&dz) {
+                return error(format!("Vertex {:?} is too far from the previous vertex.", vertex));
+            }
+            vertex_flags.p1((dx != 0) as u8 | ((dy != 0) as u8) << 1 | ((dz != 0) as u8) << 2);
+            if dx != 0 {
+                xs.psmart_s16(dx);
+            }
+            if dy != 0 {
+                ys.psmart_s16(dy);
+            }
+            if dz != 0 {
+                zs.psmart_s16(dz);
+            }
+            previous = *vertex;
+        }
+
+        let mut compression_types = Packet::empty();
+        let mut indices = Packet::empty();
+        let mut colours = Packet::empty();
+        let (mut a, mut b, mut c) = (0i32, 0i32, 0i32);
+        for face in &self.faces {
+            let (fa, fb, fc) = (face.a as i32, face.b as i32, face.c as i32);
+            let deltas: &[i32] = if (fa, fb) == (a, c) {
+                compression_types.p1(2);
+                &[fc - c]
+            } else if (fa, fb) == (c, b) {
+                compression_types.p1(3);
+                &[fc - c]
+            } else if (fa, fb) == (b, a) {
+                compression_types.p1(4);
+                &[fc - c]
+            } else {
+                compression_types.p1(1);
+                &[fa - c, fb - fa, fc - fb]
+            };
+            if let Some(delta) = deltas.iter().find(|delta| !DELTA_RANGE.contains(delta)) {
+                return error(format!("Face index delta {} does not fit in a smart.", delta));
+            }
+            deltas.iter().for_each(|delta| indices.psmart_s16(*delta));
+            colours.p2(face.colour);
+            (a, b, c) = (fa, fb, fc);
+        }
+
+        let mut face_textures = Packet::empty();
+        let mut coords = Packet::empty();
+        if let Some(textures) = &self.face_textures {
+            for (i, texture) in textures.iter().enumerate() {
+                face_textures.p2(texture.map_or(0, |texture| texture + 1));
+                if let (Some(_), Some(coords_of)) = (texture, &self.face_texture_coords) {
+                    coords.p1(coords_of[i].map_or(0, |coord| coord + 1));
+                }
+            }
+        }
+
+        let mut texture_faces = Packet::empty();
+        for face in &self.texture_faces {
+            texture_faces.p2(face.p);
+            texture_faces.p2(face.m);
+            texture_faces.p2(face.n);
+        }
+
+        for section in [
+            &mut vertex_flags,
+            &mut compression_types,
+            &mut indices,
+            &mut face_textures,
+            &mut coords,
+            &mut colours,
+            &mut texture_faces,
+            &mut xs,
+            &mut ys,
+            &mut zs,
+        ] {
+            section.set_pos(0)?;
+        }
+
+        let mut sections: Vec<&[u8]> = Vec::new();
+        let mapping_types = vec![0; self.texture_faces.len()];
+        if new {
+            sections.push(&mapping_types);
+        }
+        sections.push(vertex_flags.as_ref());
+        if new {
+            sections.extend(self.face_types.as_deref());
+        }
+        sections.push(compression_types.as_ref());
+        sections.extend(self.face_priorities.as_deref());
+        sections.extend(self.face_labels.as_deref());
+        if !new {
+            sections.extend(self.face_types.as_deref());
+        }
+        sections.extend(self.vertex_labels.as_deref());
+        sections.extend(self.face_alphas.as_deref());
+        sections.push(indices.as_ref());
+        if new {
+            sections.push(face_textures.as_ref());
+            sections.push(coords.as_ref());
+        }
+        sections.push(colours.as_ref());
+        sections.push(texture_faces.as_ref());
+        sections.push(xs.as_ref());
+        sections.push(ys.as_ref());
+        sections.push(zs.as_ref());
+        for section in sections {
+            packet.write_all(section)?;
+        }
+
+        packet.p2(self.vertices.len() as u16);
+        packet.p2(self.faces.len() as u16);
+        packet.p1(self.texture_faces.len() as u8);
+        packet.p1(self.face_types.is_some() as u8);
+        packet.p1(if self.face_priorities.is_some() { FACE_PRIORITIES } else { self.priority });
+        packet.p1(self.face_alphas.is_some() as u8);
+        packet.p1(self.face_labels.is_some() as u8);
+        if new {
+            packet.p1(self.face_textures.is_some() as u8);
+        }
+        packet.p1(self.vertex_labels.is_some() as u8);
+        for len in [xs.len(), ys.len(), zs.len(), indices.len()] {
+            packet.p2(len as u16);
+        }
+        if new {
+            packet.p2(coords.len() as u16);
+            packet.write_all(&NEW_FORMAT_MARKER)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every count fits in the header and every optional section matches the
+    /// amount of vertices or faces.
+    fn validate(&self) -> Result<(), PacketError> {
+        if self.vertices.len() > u16::MAX as usize || self.faces.len() > u16::MAX as usize {
+            return error(format!("Too many vertices ({}) or faces ({}).", self.vertices.len(), self.faces.len()));
+        }
+        if self.texture_faces.len() > u8::MAX as usize {
+            return error(format!("Too many texture faces ({}).", self.texture_faces.len()));
+        }
+        if self.face_priorities.is_none() && self.priority == FACE_PRIORITIES {
+            return error(format!("Model priority {} requires face priorities.", FACE_PRIORITIES));
+        }
+        if self.format == ModelFormat::Legacy && (self.face_textures.is_some() || self.face_texture_coords.is_some()) {
+            return error("Legacy models cannot hold face textures.".to_string());
+        }
+        if self.face_texture_coords.is_some() && (self.face_textures.is_none() || self.texture_faces.is_empty()) {
+            return error("Face texture coords require face textures and texture faces.".to_string());
+        }
+        if self.face_textures.is_some() && !self.texture_faces.is_empty() && self.face_texture_coords.is_none() {
+            return error("Face textures of a model with texture faces require face texture coords.".to_string());
+        }
+        if self.face_textures.iter().flatten().any(|texture| *texture == Some(u16::MAX))
+            || self.face_texture_coords.iter().flatten().any(|coord| *coord == Some(u8::MAX))
+        {
+            return error("Face texture or texture coord out of range.".to_string());
+        }
+
+        let vertex_sections = [self.vertex_labels.as_ref().map(Vec::len)];
+        let face_sections = [
+            self.face_types.as_ref().map(Vec::len),
+            self.face_priorities.as_ref().map(Vec::len),
+            self.face_alphas.as_ref().map(Vec::len),
+            self.face_labels.as_ref().map(Vec::len),
+            self.face_textures.as_ref().map(Vec::len),
+            self.face_texture_coords.as_ref().map(Vec::len),
+        ];
+        if vertex_sections.into_iter().flatten().any(|len| len != self.vertices.len())
+            || face_sections.into_iter().flatten().any(|len| len != self.faces.len())
+        {
+            return error("Model section lengths do not match the vertex or face count.".to_string());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::io::Write;
+
+/// The opcode ending a track.
+const END_OF_TRACK: u8 = 7;
+
+/// The opcode setting the tempo.
+const TEMPO: u8 = 23;
+
+/// The MIDI file header chunk id, `MThd`.
+const MIDI_HEADER: u32 = 0x4D546864;
+
+/// The MIDI track chunk id, `MTrk`.
+const MIDI_TRACK: u32 = 0x4D54726B;
+
+/// The kind of a [MidiEvent], carrying its channel and data bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiEventKind {
+    NoteOff { channel: u8, key: u8, velocity: u8 },
+    NoteOn { channel: u8, key: u8, velocity: u8 },
+    KeyPressure { channel: u8, key: u8, pressure: u8 },
+    Controller { channel: u8, controller: u8, value: u8 },
+    ProgramChange { channel: u8, program: u8 },
+    ChannelPressure { channel: u8, pressure: u8 },
+    /// The 14-bit pitch bend, centred on `8192`.
+    PitchBend { channel: u8, value: u16 },
+    /// The microseconds per quarter note.
+    Tempo(u32),
+    EndOfTrack,
+}
+
+impl MidiEventKind {
+    /// Writes the event as it appears in a standard MIDI file, always including the status.
+    fn write(&self, packet: &mut Packet) {
+        let mut channel_event = |status: u8, channel: u8, data: &[u8]| {
+            packet.p1(status | channel);
+            data.iter().for_each(|byte| packet.p1(*byte));
+        };
+        match *self {
+            MidiEventKind::NoteOff { channel, key, velocity } => channel_event(0x80, channel, &[key, velocity]),
+            MidiEventKind::NoteOn { channel, key, velocity } => channel_event(0x90, channel, &[key, velocity]),
+            MidiEventKind::KeyPressure { channel, key, pressure } => channel_event(0xA0, channel, &[key, pressure]),
+            MidiEventKind::Controller { channel, controller, value } => channel_event(0xB0, channel, &[controller, value]),
+            MidiEventKind::ProgramChange { channel, program } => channel_event(0xC0, channel, &[program]),
+            MidiEventKind::ChannelPressure { channel, pressure } => channel_event(0xD0, channel, &[pressure]),
+            MidiEventKind::PitchBend { channel, value } => {
+                channel_event(0xE0, channel, &[(value & 0x7F) as u8, (value >> 7 & 0x7F) as u8])
+            }
+            MidiEventKind::Tempo(tempo) => {
+                packet.p1(0xFF);
+                packet.p1(0x51);
+                packet.p1(3);
+                packet.p3(tempo);
+            }
+            MidiEventKind::EndOfTrack => {
+                packet.p1(0xFF);
+                packet.p1(0x2F);
+                packet.p1(0);
+            }
+        }
+    }
+}
+
+/// An event of a [MusicTrack], played `delta` ticks after the previous event of its track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiEvent {
+    pub delta: u32,
+    pub kind: MidiEventKind,
+}
+
+/// The sections of a [MusicTrack] holding event data, in the order they are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    /// Sustain, portamento and all notes off style controllers.
+    Switches,
+    KeyPressure,
+    ChannelPressure,
+    PitchBendMsb,
+    Modulation,
+    Volume,
+    Pan,
+    /// The keys of note on, note off and key pressure events.
+    Keys,
+    NoteOnVelocity,
+    OtherControllers,
+    NoteOffVelocity,
+    ModulationLsb,
+    VolumeLsb,
+    PanLsb,
+    /// Program changes and bank selects.
+    Programs,
+    PitchBendLsb,
+    NrpnMsb,
+    NrpnLsb,
+    RpnMsb,
+    RpnLsb,
+    Tempo,
+}
+
+impl Section {
+    const COUNT: usize = Section::Tempo as usize + 1;
+
+    /// Returns the section holding the values of `controller`.
+    fn of_controller(controller: u8) -> Section {
+        match controller {
+            0 | 32 => Section::Programs,
+            1 => Section::Modulation,
+            33 => Section::ModulationLsb,
+            7 => Section::Volume,
+            39 => Section::VolumeLsb,
+            10 => Section::Pan,
+            42 => Section::PanLsb,
+            99 => Section::NrpnMsb,
+            98 => Section::NrpnLsb,
+            101 => Section::RpnMsb,
+            100 => Section::RpnLsb,
+            64 | 65 | 120 | 121 | 123 => Section::Switches,
+            _ => Section::OtherControllers,
+        }
+    }
+}
+
+/// A music track, stored in the music archive as a compacted MIDI file.
+///
+/// Every event is reduced to an opcode, where the low nibble holds the event type and the high
+/// nibble is xored into the running channel. The opcodes of every track come first, followed
+/// by the delta time of every event as a `gvar_u32`, the controller numbers as deltas, and then
+/// the data bytes of every event grouped into sections by their meaning, most of them as
+/// deltas from the previous value. The file ends with `g1` track count and `g2` ticks per
+/// quarter note.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MusicTrack {
+    pub division: u16,
+    pub tracks: Vec<Vec<MidiEvent>>,
+}
+
+impl MusicTrack {
+    pub fn decode(packet: &mut Packet) -> Result<MusicTrack, PacketError> {
+        let data = packet.slice_remaining().to_vec();
+        packet.skip(data.len());
+        if data.len() < 3 {
+            return error(format!("Music track of {} bytes is too short.", data.len()));
+        }
+        let mut reader = Packet::from(&data[..]);
+        let (track_count, division) = reader.with_pos(data.len() - 3, |reader| Ok((reader.g1()? as usize, reader.g2()?)))?;
+        let mut sizes = [0usize; Section::COUNT];
+        let mut opcodes = Vec::new();
+        let mut controllers = 0;
+        let mut notes = [0usize; 2];
+        for _ in 0..track_count {
+            loop {
+                let opcode = reader.g1()?;
+                opcodes.push(opcode);
+                match (opcode, opcode & 0xF) {
+                    (END_OF_TRACK, _) => break,
+                    (TEMPO, _) => sizes[Section::Tempo as usize] += 3,
+                    (_, 0) => notes[0] += 1,
+                    (_, 1) => notes[1] += 1,
+                    (_, 2) => controllers += 1,
+                    (_, 3) => {
+                        sizes[Section::PitchBendMsb as usize] += 1;
+                        sizes[Section::PitchBendLsb as usize] += 1;
+                    }
+                    (_, 4) => sizes[Section::ChannelPressure as usize] += 1,
+                    (_, 5) => {
+                        sizes[Section::Keys as usize] += 1;
+                        sizes[Section::KeyPressure as usize] += 1;
+                    }
+                    (_, 6) => sizes[Section::Programs as usize] += 1,
+                    _ => return error(format!("Unknown music opcode {}.", opcode)),
+                }
+            }
+        }
+        sizes[Section::Keys as usize] += notes[0] + notes[1];
+        sizes[Section::NoteOnVelocity as usize] += notes[0];
+        sizes[Section::NoteOffVelocity as usize] += notes[1];
+
+        let deltas = (0..opcodes.len()).map(|_| reader.gvar_u32()).collect::<Result<Vec<_>, _>>()?;
+
+        let controller_start = reader.get_pos();
+        let mut controller = 0u8;
+        for _ in 0..controllers {
+            controller = controller.wrapping_add(reader.g1()?) & 0x7F;
+            sizes[Section::of_controller(controller) as usize] += 1;
+        }
+
+        let mut cursors = [0usize; Section::COUNT];
+        let mut offset = reader.get_pos();
+        for (cursor, size) in cursors.iter_mut().zip(sizes) {
+            *cursor = offset;
+            offset += size;
+        }
+        if offset > data.len() - 3 {
+            return error(format!("Music track sections of {} bytes exceed the track.", offset));
+        }
+
+        let mut next = |section: Section| {
+            let cursor = &mut cursors[section as usize];
+            *cursor += 1;
+            data[*cursor - 1] as i8 as i32
+        };
+
+        let mut controller_cursor = controller_start;
+        let (mut channel, mut key, mut on_velocity, mut off_velocity) = (0u8, 0i32, 0i32, 0i32);
+        let (mut pitch_bend, mut channel_pressure, mut key_pressure) = (0i32, 0i32, 0i32);
+        let mut values = [0i32; 128];
+        let mut controller = 0u8;
+
+        let mut events = opcodes.into_iter().zip(deltas);
+        let mut tracks = Vec::with_capacity(track_count);
+        for _ in 0..track_count {
+            let mut track = Vec::new();
+            for (opcode, delta) in events.by_ref() {
+                let kind = match opcode {
+                    END_OF_TRACK => MidiEventKind::EndOfTrack,
+                    TEMPO => {
+                        let tempo = (0..3).fold(0u32, |tempo, _| tempo << 8 | next(Section::Tempo) as u8 as u32);
+                        MidiEventKind::Tempo(tempo)
+                    }
+                    _ => {
+                        channel ^= opcode >> 4;
+                        match opcode & 0xF {
+                            0 => {
+                                key += next(Section::Keys);
+                                on_velocity += next(Section::NoteOnVelocity);
+                                MidiEventKind::NoteOn {
+                                    channel,
+                                    key: (key & 0x7F) as u8,
+                                    velocity: (on_velocity & 0x7F) as u8,
+                                }
+                            }
+                            1 => {
+                                key += next(Section::Keys);
+                                off_velocity += next(Section::NoteOffVelocity);
+                                MidiEventKind::NoteOff {
+                                    channel,
+                                    key: (key & 0x7F) as u8,
+                                    velocity: (off_velocity & 0x7F) as u8,
+                                }
+                            }
+                            2 => {
+                                controller = controller.wrapping_add(data[controller_cursor]) & 0x7F;
+                                controller_cursor += 1;
+                                let value = &mut values[controller as usize];
+                                *value += next(Section::of_controller(controller));
+                                MidiEventKind::Controller {
+                                    channel,
+                                    controller,
+                                    value: (*value & 0x7F) as u8,
+                                }
+                            }
+                            3 => {
+                                pitch_bend += next(Section::PitchBendLsb);
+                                pitch_bend += next(Section::PitchBendMsb) << 7;
+                                MidiEventKind::PitchBend {
+                                    channel,
+                                    value: (pitch_bend & 0x3FFF) as u16,
+                                }
+                            }
+                            4 => {
+                                channel_pressure += next(Section::ChannelPressure);
+                                MidiEventKind::ChannelPressure {
+                                    channel,
+                                    pressure: (channel_pressure & 0x7F) as u8,
+                                }
+                            }
+                            5 => {
+                                key += next(Section::Keys);
+                                key_pressure += next(Section::KeyPressure);
+                                MidiEventKind::KeyPressure {
+                                    channel,
+                                    key: (key & 0x7F) as u8,
+                                    pressure: (key_pressure & 0x7F) as u8,
+                                }
+                            }
+                            _ => MidiEventKind::ProgramChange {
+                                channel,
+                                program: (next(Section::Programs) & 0x7F) as u8,
+                            },
+                        }
+                    }
+                };
+                track.push(MidiEvent { delta, kind });
+                if kind == MidiEventKind::EndOfTrack {
+                    break;
+                }
+            }
+            tracks.push(track);
+        }
+
+        Ok(MusicTrack { division, tracks })
+    }
+
+    /// Converts the track to a standard MIDI file, of format `1` when holding more than one
+    /// track and `0` otherwise.
+    pub fn to_midi(&self) -> Result<Vec<u8>, PacketError> {
+        let mut packet = Packet::empty();
+        packet.p4(MIDI_HEADER);
+        packet.p4(6);
+        packet.p2((self.tracks.len() > 1) as u16);
+        packet.p2(self.tracks.len() as u16);
+        packet.p2(self.division);
+
+        for events in &self.tracks {
+            let mut track = Packet::empty();
+            for event in events {
+                track.pvar_u32(event.delta);
+                event.kind.write(&mut track);
+            }
+            if events.last().map(|event| event.kind) != Some(MidiEventKind::EndOfTrack) {
+                track.p1(0);
+                MidiEventKind::EndOfTrack.write(&mut track);
+            }
+            track.set_pos(0)?;
+            packet.p4(MIDI_TRACK);
+            packet.p4(track.len() as u32);
+            packet.write_all(track.as_ref())?;
+        }
+        packet.set_pos(0)?;
+        Ok(packet.to_vec())
+    }
+}
@@ -0,0 +1,291 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+const FLAG_NAMES: u8 = 0x1;
+const FLAG_DIGESTS: u8 = 0x2;
+const FLAG_LENGTHS: u8 = 0x4;
+const FLAG_UNCOMPRESSED_CHECKSUMS: u8 = 0x8;
+
+/// The length of a whirlpool digest in bytes.
+pub const DIGEST_LEN: usize = 64;
+
+/// A file within a group of a [ReferenceTable].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileEntry {
+    pub name_hash: i32,
+}
+
+/// A group within a [ReferenceTable]. Fields belonging to a flag that is not set on the table
+/// are left as zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupEntry {
+    pub name_hash: i32,
+    pub crc: u32,
+    pub uncompressed_crc: u32,
+    pub digest: [u8; DIGEST_LEN],
+    pub length: u32,
+    pub uncompressed_length: u32,
+    pub version: u32,
+    pub files: BTreeMap<u32, FileEntry>,
+}
+
+impl Default for GroupEntry {
+    fn default() -> Self {
+        Self {
+            name_hash: 0,
+            crc: 0,
+            uncompressed_crc: 0,
+            digest: [0; DIGEST_LEN],
+            length: 0,
+            uncompressed_length: 0,
+            version: 0,
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+/// The reference table of an archive, stored in archive `255`, listing every group within the
+/// archive along with the files contained in each group.
+///
+/// Protocol `5` tables carry no version, protocol `6` adds the table version, and protocol `7`
+/// switches group and file ids and counts over to [big smarts](Packet::gbig_smart).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceTable {
+    pub protocol: u8,
+    pub version: u32,
+    pub named: bool,
+    pub digests: bool,
+    pub lengths: bool,
+    pub uncompressed_checksums: bool,
+    pub groups: BTreeMap<u32, GroupEntry>,
+}
+
+impl Default for ReferenceTable {
+    fn default() -> Self {
+        Self {
+            protocol: 6,
+            version: 0,
+            named: false,
+            digests: false,
+            lengths: false,
+            uncompressed_checksums: false,
+            groups: BTreeMap::new(),
+        }
+    }
+}
+
+impl ReferenceTable {
+    /// Decodes a reference table from the packet's current position.
+    pub fn decode(packet: &mut Packet) -> Result<ReferenceTable, PacketError> {
+        let protocol = packet.g1()?;
+        if !(5..=7).contains(&protocol) {
+            return error(format!("Unsupported reference table protocol {}.", protocol));
+        }
+        let smart = protocol >= 7;
+
+        let version = if protocol >= 6 { packet.g4()? } else { 0 };
+        let flags = packet.g1()?;
+        let mut table = ReferenceTable {
+            protocol,
+            version,
+            named: flags & FLAG_NAMES != 0,
+            digests: flags & FLAG_DIGESTS != 0,
+            lengths: flags & FLAG_LENGTHS != 0,
+            uncompressed_checksums: flags & FLAG_UNCOMPRESSED_CHECKSUMS != 0,
+            groups: BTreeMap::new(),
+        };
+
+        let group_ids = read_ids(packet, smart)?;
+        let mut groups: Vec<GroupEntry> = vec![GroupEntry::default(); group_ids.len()];
+
+        if table.named {
+            for group in groups.iter_mut() {
+                group.name_hash = packet.g4s()?;
+            }
+        }
+        for group in groups.iter_mut() {
+            group.crc = packet.g4()?;
+        }
+        if table.uncompressed_checksums {
+            for group in groups.iter_mut() {
+                group.uncompressed_crc = packet.g4()?;
+            }
+        }
+        if table.digests {
+            for group in groups.iter_mut() {
+                if !packet.has_available(DIGEST_LEN) {
+                    return error("Not enough data for group digest.".to_string());
+                }
+                group.digest.copy_from_slice(&packet.gdata(DIGEST_LEN));
+            }
+        }
+        if table.lengths {
+            for group in groups.iter_mut() {
+                group.length = packet.g4()?;
+                group.uncompressed_length = packet.g4()?;
+            }
+        }
+        for group in groups.iter_mut() {
+            group.version = packet.g4()?;
+        }
+
+        let mut file_counts = Vec::with_capacity(groups.len());
+        for _ in 0..groups.len() {
+            file_counts.push(read_id(packet, smart)? as usize);
+        }
+
+        let mut file_ids = Vec::with_capacity(groups.len());
+        for count in file_counts {
+            // Counts come from the wire, so never reserve more than the remaining bytes could hold.
+            let mut ids = Vec::with_capacity(count.min(packet.available_count()));
+            let mut id = 0u32;
+            for _ in 0..count {
+                id = id.wrapping_add(read_id(packet, smart)?);
+                ids.push(id);
+            }
+            file_ids.push(ids);
+        }
+
+        for (group, ids) in groups.iter_mut().zip(&file_ids) {
+            for id in ids {
+                let name_hash = if table.named { packet.g4s()? } else { 0 };
+                group.files.insert(*id, FileEntry { name_hash });
+            }
+        }
+
+        table.groups = group_ids.into_iter().zip(groups).collect();
+        Ok(table)
+    }
+
+    /// Encodes this reference table into a new packet positioned at the start of the table.
+    pub fn encode(&self) -> Result<Packet, PacketError> {
+        if !(5..=7).contains(&self.protocol) {
+            return error(format!("Unsupported reference table protocol {}.", self.protocol));
+        }
+        let smart = self.protocol >= 7;
+
+        let mut packet = Packet::empty();
+        packet.p1(self.protocol);
+        if self.protocol >= 6 {
+            packet.p4(self.version);
+        }
+        packet.p1(self.flags());
+
+        write_ids(&mut packet, self.groups.keys(), self.groups.len(), smart);
+        if self.named {
+            for group in self.groups.values() {
+                packet.p4s(group.name_hash);
+            }
+        }
+        for group in self.groups.values() {
+            packet.p4(group.crc);
+        }
+        if self.uncompressed_checksums {
+            for group in self.groups.values() {
+                packet.p4(group.uncompressed_crc);
+            }
+        }
+        if self.digests {
+            for group in self.groups.values() {
+                packet.write_all(&group.digest)?;
+            }
+        }
+        if self.lengths {
+            for group in self.groups.values() {
+                packet.p4(group.length);
+                packet.p4(group.uncompressed_length);
+            }
+        }
+        for group in self.groups.values() {
+            packet.p4(group.version);
+        }
+        for group in self.groups.values() {
+            write_id(&mut packet, group.files.len() as u32, smart);
+        }
+        for group in self.groups.values() {
+            let mut previous = 0u32;
+            for id in group.files.keys() {
+                write_id(&mut packet, id - previous, smart);
+                previous = *id;
+            }
+        }
+        if self.named {
+            for group in self.groups.values() {
+                for file in group.files.values() {
+                    packet.p4s(file.name_hash);
+                }
+            }
+        }
+
+        packet.set_pos(0)?;
+        Ok(packet)
+    }
+
+    /// Returns the group with the given name hash, if the table is named.
+    pub fn find_group(&self, name_hash: i32) -> Option<(u32, &GroupEntry)> {
+        if !self.named {
+            return None;
+        }
+        self.groups
+            .iter()
+            .find(|(_, group)| group.name_hash == name_hash)
+            .map(|(id, group)| (*id, group))
+    }
+
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.named {
+            flags |= FLAG_NAMES;
+        }
+        if self.digests {
+            flags |= FLAG_DIGESTS;
+        }
+        if self.lengths {
+            flags |= FLAG_LENGTHS;
+        }
+        if self.uncompressed_checksums {
+            flags |= FLAG_UNCOMPRESSED_CHECKSUMS;
+        }
+        flags
+    }
+}
+
+fn read_id(packet: &mut Packet, smart: bool) -> Result<u32, PacketError> {
+    if smart {
+        packet.gbig_smart()
+    } else {
+        packet.g2().map(|value| value as u32)
+    }
+}
+
+fn write_id(packet: &mut Packet, value: u32, smart: bool) {
+    if smart {
+        packet.pbig_smart(value);
+    } else {
+        packet.p2(value as u16);
+    }
+}
+
+/// Reads a count followed by that many delta-encoded ids.
+fn read_ids(packet: &mut Packet, smart: bool) -> Result<Vec<u32>, PacketError> {
+    let count = read_id(packet, smart)? as usize;
+    let mut ids = Vec::with_capacity(count.min(packet.available_count()));
+    let mut id = 0u32;
+    for _ in 0..count {
+        id = id.wrapping_add(read_id(packet, smart)?);
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// Writes a count followed by the delta-encoded `ids`, which must be in ascending order.
+fn write_ids<'a>(packet: &mut Packet, ids: impl Iterator<Item = &'a u32>, count: usize, smart: bool) {
+    write_id(packet, count as u32, smart);
+    let mut previous = 0u32;
+    for id in ids {
+        write_id(packet, id - previous, smart);
+        previous = *id;
+    }
+}
@@ -0,0 +1,205 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// Pushes a string constant, the only opcode with a string operand.
+pub const SCONST: u16 = 3;
+
+/// Returns from the script, taking a byte operand despite being below [WIDE_OPCODE_LIMIT].
+pub const RETURN: u16 = 21;
+
+/// Pops an int from the stack, taking a byte operand despite being below [WIDE_OPCODE_LIMIT].
+pub const POP_INT_DISCARD: u16 = 38;
+
+/// Pops a string from the stack, taking a byte operand despite being below
+/// [WIDE_OPCODE_LIMIT].
+pub const POP_STRING_DISCARD: u16 = 39;
+
+/// Opcodes below this limit are core instructions taking a `g4` operand, while those at or
+/// above it are commands taking a `g1` operand.
+pub const WIDE_OPCODE_LIMIT: u16 = 100;
+
+/// The size of the fixed part of the footer: the instruction count, local counts and argument
+/// counts.
+const FOOTER_SIZE: usize = 12;
+
+/// The kind of operand an opcode takes, decided by [OperandKind::of].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandKind {
+    Int,
+    Str,
+    Byte,
+}
+
+impl OperandKind {
+    pub fn of(opcode: u16) -> OperandKind {
+        match opcode {
+            SCONST => OperandKind::Str,
+            RETURN | POP_INT_DISCARD | POP_STRING_DISCARD => OperandKind::Byte,
+            _ if opcode < WIDE_OPCODE_LIMIT => OperandKind::Int,
+            _ => OperandKind::Byte,
+        }
+    }
+}
+
+/// The operand of an [Instruction].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Int(i32),
+    Str(String),
+    Byte(u8),
+}
+
+impl Operand {
+    pub fn kind(&self) -> OperandKind {
+        match self {
+            Operand::Int(_) => OperandKind::Int,
+            Operand::Str(_) => OperandKind::Str,
+            Operand::Byte(_) => OperandKind::Byte,
+        }
+    }
+}
+
+/// A single instruction of a [ClientScript]: `g2` opcode, then the operand of the
+/// [kind](OperandKind::of) the opcode takes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: u16,
+    pub operand: Operand,
+}
+
+impl Instruction {
+    pub fn new(opcode: u16, operand: Operand) -> Self {
+        Self { opcode, operand }
+    }
+}
+
+/// A client script (cs2), stored in the clientscripts archive.
+///
+/// - `gjstr` name, or a single `0` byte if unnamed
+/// - the [instructions](Instruction)
+/// - `g4` instruction count, `g2` int locals, `g2` string locals, `g2` int arguments and `g2`
+///   string arguments
+/// - `g1` switch count, then `g2` case count, and `g4` key and `g4` jump per case per switch
+/// - `g2` length of the switch section
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientScript {
+    pub name: Option<String>,
+    pub instructions: Vec<Instruction>,
+    pub int_locals: u16,
+    pub string_locals: u16,
+    pub int_args: u16,
+    pub string_args: u16,
+    /// The switch tables indexed by the operand of a switch instruction, each holding the key
+    /// and relative jump of every case in order.
+    pub switches: Vec<Vec<(i32, i32)>>,
+}
+
+impl ClientScript {
+    pub fn decode(packet: &mut Packet) -> Result<ClientScript, PacketError> {
+        packet.seek_from_end(2)?;
+        let switch_len = packet.g2()? as usize;
+        packet.seek_from_end(2 + switch_len + FOOTER_SIZE)?;
+        let end = packet.get_pos();
+        let count = packet.g4()? as usize;
+        let int_locals = packet.g2()?;
+        let string_locals = packet.g2()?;
+        let int_args = packet.g2()?;
+        let string_args = packet.g2()?;
+        let switch_count = packet.g1()?;
+        let switches = (0..switch_count)
+            .map(|_| {
+                let cases = packet.g2()?;
+                (0..cases).map(|_| Ok((packet.g4s()?, packet.g4s()?))).collect::<Result<_, PacketError>>()
+            })
+            .collect::<Result<_, _>>()?;
+
+        packet.set_pos(0)?;
+        let name = match packet.peek() {
+            Some(0) => {
+                packet.skip(1);
+                None
+            }
+            _ => Some(packet.gjstr()?),
+        };
+
+        let mut instructions = Vec::with_capacity(count.min(end));
+        while packet.get_pos() < end {
+            let opcode = packet.g2()?;
+            let operand = match OperandKind::of(opcode) {
+                OperandKind::Int => Operand::Int(packet.g4s()?),
+                OperandKind::Str => Operand::Str(packet.gjstr()?),
+                OperandKind::Byte => Operand::Byte(packet.g1()?),
+            };
+            instructions.push(Instruction { opcode, operand });
+        }
+        if packet.get_pos() != end || instructions.len() != count {
+            return error(format!(
+                "Client script declares {} instructions but {} were read.",
+                count,
+                instructions.len()
+            ));
+        }
+
+        Ok(ClientScript {
+            name,
+            instructions,
+            int_locals,
+            string_locals,
+            int_args,
+            string_args,
+            switches,
+        })
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if let Some(instruction) = self.instructions.iter().find(|i| i.operand.kind() != OperandKind::of(i.opcode)) {
+            return error(format!(
+                "Opcode {} takes a {:?} operand but was given {:?}.",
+                instruction.opcode,
+                OperandKind::of(instruction.opcode),
+                instruction.operand
+            ));
+        }
+        if self.switches.len() > u8::MAX as usize {
+            return error(format!("Too many switch tables ({}).", self.switches.len()));
+        }
+        if let Some(cases) = self.switches.iter().find(|cases| cases.len() > u16::MAX as usize) {
+            return error(format!("Too many switch cases ({}).", cases.len()));
+        }
+
+        match &self.name {
+            Some(name) => packet.pjstr(name),
+            None => packet.p1(0),
+        }
+        for instruction in &self.instructions {
+            packet.p2(instruction.opcode);
+            match &instruction.operand {
+                Operand::Int(value) => packet.p4s(*value),
+                Operand::Str(value) => packet.pjstr(value),
+                Operand::Byte(value) => packet.p1(*value),
+            }
+        }
+
+        packet.p4(self.instructions.len() as u32);
+        packet.p2(self.int_locals);
+        packet.p2(self.string_locals);
+        packet.p2(self.int_args);
+        packet.p2(self.string_args);
+
+        let switch_start = packet.writer_index();
+        packet.p1(self.switches.len() as u8);
+        for cases in &self.switches {
+            packet.p2(cases.len() as u16);
+            for (key, jump) in cases {
+                packet.p4s(*key);
+                packet.p4s(*jump);
+            }
+        }
+        let switch_len = packet.writer_index() - switch_start;
+        if switch_len > u16::MAX as usize {
+            return error(format!("Switch section of {} bytes is too long.", switch_len));
+        }
+        packet.p2(switch_len as u16);
+        Ok(())
+    }
+}
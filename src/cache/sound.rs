@@ -0,0 +1,333 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// The amount of instruments a sound effect holds.
+pub const INSTRUMENTS: usize = 10;
+
+/// The most oscillators an instrument holds.
+pub const OSCILLATORS: usize = 10;
+
+/// The most poles a filter holds in each direction while still being able to migrate them.
+pub const FILTER_POLES: usize = 4;
+
+/// A series of segments interpolating between a start and end value over the duration of an
+/// instrument.
+///
+/// - `g1` waveform, `g4` start, `g4` end
+/// - `g1` count, then `g2` duration and `g2` phase per segment
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Envelope {
+    /// The waveform the envelope drives, where `0` marks an absent envelope and is therefore
+    /// never sent.
+    pub form: u8,
+    pub start: i32,
+    pub end: i32,
+    pub segments: Vec<(u16, u16)>,
+}
+
+impl Envelope {
+    pub fn decode(packet: &mut Packet) -> Result<Envelope, PacketError> {
+        Ok(Envelope {
+            form: packet.g1()?,
+            start: packet.g4s()?,
+            end: packet.g4s()?,
+            segments: decode_segments(packet)?,
+        })
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        packet.p1(self.form);
+        packet.p4s(self.start);
+        packet.p4s(self.end);
+        encode_segments(packet, &self.segments)
+    }
+
+    /// Reads a pair of envelopes if present, signalled by a non-zero form.
+    fn decode_pair(packet: &mut Packet) -> Result<Option<(Envelope, Envelope)>, PacketError> {
+        if packet.peek() == Some(0) {
+            packet.skip(1);
+            return Ok(None);
+        }
+        Ok(Some((Envelope::decode(packet)?, Envelope::decode(packet)?)))
+    }
+
+    fn encode_pair(packet: &mut Packet, pair: &Option<(Envelope, Envelope)>) -> Result<(), PacketError> {
+        match pair {
+            Some((first, second)) => {
+                if first.form == 0 {
+                    return error("Optional envelopes must have a non-zero form.".to_string());
+                }
+                first.encode(packet)?;
+                second.encode(packet)
+            }
+            None => {
+                packet.p1(0);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A pole of a [Filter], which may migrate to a second phase and magnitude over the filter
+/// envelope.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterPole {
+    pub phase: u16,
+    pub magnitude: u16,
+    pub migrated: Option<(u16, u16)>,
+}
+
+/// The filter of an [Instrument], holding poles in the feedforward and feedback directions.
+///
+/// - `g1` pole counts, feedforward in the high nibble, where `0` ends the filter
+/// - `g2` start unity, `g2` end unity, `g1` migration mask
+/// - `g2` phase and `g2` magnitude per pole per direction
+/// - `g2` phase and `g2` magnitude per migrated pole per direction
+/// - the segments of the filter envelope, if any pole migrates or the unity changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    pub unity: [u16; 2],
+    pub poles: [Vec<FilterPole>; 2],
+    pub envelope: Option<Vec<(u16, u16)>>,
+}
+
+impl Filter {
+    pub fn decode(packet: &mut Packet) -> Result<Filter, PacketError> {
+        let counts = packet.g1()?;
+        if counts == 0 {
+            return Ok(Filter::default());
+        }
+        let counts = [(counts >> 4) as usize, (counts & 0xF) as usize];
+        let unity = [packet.g2()?, packet.g2()?];
+        let migrated = packet.g1()? as u32;
+
+        let mut poles = [Vec::new(), Vec::new()];
+        for (direction, poles) in poles.iter_mut().enumerate() {
+            for _ in 0..counts[direction] {
+                poles.push(FilterPole {
+                    phase: packet.g2()?,
+                    magnitude: packet.g2()?,
+                    migrated: None,
+                });
+            }
+        }
+        for (direction, poles) in poles.iter_mut().enumerate() {
+            for (i, pole) in poles.iter_mut().enumerate() {
+                if migrated & 1 << (direction * 4 + i) != 0 {
+                    pole.migrated = Some((packet.g2()?, packet.g2()?));
+                }
+            }
+        }
+
+        let envelope = if migrated != 0 || unity[0] != unity[1] {
+            Some(decode_segments(packet)?)
+        } else {
+            None
+        };
+        Ok(Filter { unity, poles, envelope })
+    }
+
+    /// Encodes the filter. The envelope is written as empty when required but absent, and
+    /// dropped when not required.
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if self.poles.iter().any(|poles| poles.len() > FILTER_POLES) {
+            return error(format!("Filters hold at most {} poles per direction.", FILTER_POLES));
+        }
+        let counts = (self.poles[0].len() << 4 | self.poles[1].len()) as u8;
+        packet.p1(counts);
+        if counts == 0 {
+            return Ok(());
+        }
+
+        let mut migrated = 0u8;
+        for (direction, poles) in self.poles.iter().enumerate() {
+            for (i, pole) in poles.iter().enumerate() {
+                if pole.migrated.is_some() {
+                    migrated |= 1 << (direction * 4 + i);
+                }
+            }
+        }
+        packet.p2(self.unity[0]);
+        packet.p2(self.unity[1]);
+        packet.p1(migrated);
+        for pole in self.poles.iter().flatten() {
+            packet.p2(pole.phase);
+            packet.p2(pole.magnitude);
+        }
+        for (phase, magnitude) in self.poles.iter().flatten().filter_map(|pole| pole.migrated) {
+            packet.p2(phase);
+            packet.p2(magnitude);
+        }
+        if migrated != 0 || self.unity[0] != self.unity[1] {
+            encode_segments(packet, self.envelope.as_deref().unwrap_or_default())?;
+        }
+        Ok(())
+    }
+}
+
+/// A tone generator of an [Instrument], played at an offset from the pitch of the instrument.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Oscillator {
+    /// The volume of the oscillator, where `0` ends the oscillators and is therefore never
+    /// sent.
+    pub volume: u16,
+    pub pitch: i32,
+    pub delay: u16,
+}
+
+/// A single synthesized voice of a [SoundEffect].
+///
+/// - the pitch and volume envelopes
+/// - the pitch modifier, volume modifier and release envelope pairs, each starting with `0`
+///   when absent
+/// - `gsmart` volume, `gsmart_s16` pitch and `gsmart` delay per oscillator, ending with a
+///   volume of `0` when fewer than [OSCILLATORS] are present
+/// - `gsmart` delay time, `gsmart` delay decay, `g2` duration, `g2` offset
+/// - the [filter](Filter)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Instrument {
+    pub pitch: Envelope,
+    pub volume: Envelope,
+    /// The vibrato rate and depth.
+    pub pitch_modifier: Option<(Envelope, Envelope)>,
+    /// The tremolo rate and depth.
+    pub volume_modifier: Option<(Envelope, Envelope)>,
+    /// The gating release and attack.
+    pub release: Option<(Envelope, Envelope)>,
+    pub oscillators: Vec<Oscillator>,
+    pub delay_time: u16,
+    pub delay_decay: u16,
+    pub duration: u16,
+    pub offset: u16,
+    pub filter: Filter,
+}
+
+impl Instrument {
+    pub fn decode(packet: &mut Packet) -> Result<Instrument, PacketError> {
+        let pitch = Envelope::decode(packet)?;
+        let volume = Envelope::decode(packet)?;
+        let pitch_modifier = Envelope::decode_pair(packet)?;
+        let volume_modifier = Envelope::decode_pair(packet)?;
+        let release = Envelope::decode_pair(packet)?;
+
+        let mut oscillators = Vec::new();
+        while oscillators.len() < OSCILLATORS {
+            let volume = packet.gsmart_u16()? as u16;
+            if volume == 0 {
+                break;
+            }
+            oscillators.push(Oscillator {
+                volume,
+                pitch: packet.gsmart_s16()?,
+                delay: packet.gsmart_u16()? as u16,
+            });
+        }
+
+        Ok(Instrument {
+            pitch,
+            volume,
+            pitch_modifier,
+            volume_modifier,
+            release,
+            oscillators,
+            delay_time: packet.gsmart_u16()? as u16,
+            delay_decay: packet.gsmart_u16()? as u16,
+            duration: packet.g2()?,
+            offset: packet.g2()?,
+            filter: Filter::decode(packet)?,
+        })
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if self.pitch.form == 0 {
+            return error("The pitch envelope of an instrument must have a non-zero form.".to_string());
+        }
+        if self.oscillators.len() > OSCILLATORS {
+            return error(format!("Too many oscillators ({}).", self.oscillators.len()));
+        }
+        let smarts = self.oscillators.iter().flat_map(|o| [o.volume, o.delay]).chain([self.delay_time, self.delay_decay]);
+        if smarts.into_iter().any(|value| value > 0x7FFF) {
+            return error("Instrument value does not fit in a smart.".to_string());
+        }
+        if self.oscillators.iter().any(|o| o.volume == 0 || !(-16384..=16383).contains(&o.pitch)) {
+            return error("Oscillators need a non-zero volume and a pitch fitting in a smart.".to_string());
+        }
+
+        self.pitch.encode(packet)?;
+        self.volume.encode(packet)?;
+        Envelope::encode_pair(packet, &self.pitch_modifier)?;
+        Envelope::encode_pair(packet, &self.volume_modifier)?;
+        Envelope::encode_pair(packet, &self.release)?;
+        for oscillator in &self.oscillators {
+            packet.psmart_u16(oscillator.volume as usize);
+            packet.psmart_s16(oscillator.pitch);
+            packet.psmart_u16(oscillator.delay as usize);
+        }
+        if self.oscillators.len() < OSCILLATORS {
+            packet.psmart_u16(0);
+        }
+        packet.psmart_u16(self.delay_time as usize);
+        packet.psmart_u16(self.delay_decay as usize);
+        packet.p2(self.duration);
+        packet.p2(self.offset);
+        self.filter.encode(packet)
+    }
+}
+
+/// A synthesized sound effect, stored in the sound effects archive.
+///
+/// - [INSTRUMENTS] instruments, each a single `0` byte when absent
+/// - `g2` loop start, `g2` loop end
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SoundEffect {
+    pub instruments: [Option<Instrument>; INSTRUMENTS],
+    pub loop_start: u16,
+    pub loop_end: u16,
+}
+
+impl SoundEffect {
+    pub fn decode(packet: &mut Packet) -> Result<SoundEffect, PacketError> {
+        let mut instruments: [Option<Instrument>; INSTRUMENTS] = Default::default();
+        for instrument in instruments.iter_mut() {
+            if packet.peek() == Some(0) {
+                packet.skip(1);
+            } else {
+                *instrument = Some(Instrument::decode(packet)?);
+            }
+        }
+        Ok(SoundEffect {
+            instruments,
+            loop_start: packet.g2()?,
+            loop_end: packet.g2()?,
+        })
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        for instrument in &self.instruments {
+            match instrument {
+                Some(instrument) => instrument.encode(packet)?,
+                None => packet.p1(0),
+            }
+        }
+        packet.p2(self.loop_start);
+        packet.p2(self.loop_end);
+        Ok(())
+    }
+}
+
+fn decode_segments(packet: &mut Packet) -> Result<Vec<(u16, u16)>, PacketError> {
+    let count = packet.g1()?;
+    (0..count).map(|_| Ok((packet.g2()?, packet.g2()?))).collect()
+}
+
+fn encode_segments(packet: &mut Packet, segments: &[(u16, u16)]) -> Result<(), PacketError> {
+    if segments.len() > u8::MAX as usize {
+        return error(format!("Too many envelope segments ({}).", segments.len()));
+    }
+    packet.p1(segments.len() as u8);
+    for (duration, phase) in segments {
+        packet.p2(*duration);
+        packet.p2(*phase);
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::collections::HashMap;
+
+/// Set when the palette indices of a frame are stored column by column.
+const FLAG_VERTICAL: u8 = 1;
+
+/// Set when a frame stores an alpha value per pixel after its palette indices.
+const FLAG_ALPHA: u8 = 2;
+
+/// The most colours a palette holds, excluding the transparent colour at index `0`.
+pub const MAX_PALETTE_COLOURS: usize = 255;
+
+/// A single frame of a [SpriteSheet], drawn at an offset within the sheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sprite {
+    pub offset_x: u16,
+    pub offset_y: u16,
+    pub width: u16,
+    pub height: u16,
+    /// The pixels of the frame row by row, as 4 bytes of red, green, blue and alpha each.
+    pub pixels: Vec<u8>,
+}
+
+impl Sprite {
+    /// Returns the red, green, blue and alpha of the pixel at `x`, `y`.
+    pub fn get(&self, x: u16, y: u16) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels.get(index..index + 4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+    }
+}
+
+/// A group of sprite frames sharing a palette, stored in the sprites archive.
+///
+/// - the palette indices of each frame, preceded by `g1` flags and followed by its alphas if
+///   [FLAG_ALPHA] is set
+/// - `g3` per palette colour, excluding the transparent colour at index `0`
+/// - `g2` width, `g2` height, `g1` palette colour count
+/// - `g2` offset x, `g2` offset y, `g2` width and `g2` height per frame, each as a separate list
+/// - `g2` frame count
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpriteSheet {
+    pub width: u16,
+    pub height: u16,
+    pub frames: Vec<Sprite>,
+}
+
+impl SpriteSheet {
+    pub fn decode(packet: &mut Packet) -> Result<SpriteSheet, PacketError> {
+        packet.seek_from_end(2)?;
+        let count = packet.g2()? as usize;
+
+        packet.seek_from_end(7 + count * 8)?;
+        let trailer = packet.get_pos();
+        let width = packet.g2()?;
+        let height = packet.g2()?;
+        let colours = packet.g1()? as usize;
+        let mut dimensions = [vec![0u16; count], vec![0u16; count], vec![0u16; count], vec![0u16; count]];
+        for values in dimensions.iter_mut() {
+            for value in values.iter_mut() {
+                *value = packet.g2()?;
+            }
+        }
+
+        let Some(palette_start) = trailer.checked_sub(colours * 3) else {
+            return error(format!("Sprite sheet palette of {} colours exceeds the sheet.", colours));
+        };
+        let palette = packet.with_pos(palette_start, |packet| {
+            let mut palette = vec![0u32; colours + 1];
+            for colour in palette.iter_mut().skip(1) {
+                // A colour of 0 would be transparent, so the client nudges it to the closest black.
+                *colour = (packet.g3()? as u32).max(1);
+            }
+            Ok(palette)
+        })?;
+
+        packet.set_pos(0)?;
+        let [offsets_x, offsets_y, widths, heights] = dimensions;
+        let mut frames = Vec::with_capacity(count);
+        for i in 0..count {
+            let (frame_width, frame_height) = (widths[i] as usize, heights[i] as usize);
+            let area = frame_width * frame_height;
+            let flags = packet.g1()?;
+            // The dimensions come from the trailer, so check the frame fits before the palette
+            // ahead of allocating for it.
+            let needed = if flags & FLAG_ALPHA != 0 { area.saturating_mul(2) } else { area };
+            if needed > palette_start.saturating_sub(packet.get_pos()) {
+                return error(format!("Sprite frame of {}x{} exceeds the sheet.", frame_width, frame_height));
+            }
+            let order = |j: usize| match flags & FLAG_VERTICAL {
+                0 => j,
+                _ => (j % frame_height) * frame_width + j / frame_height,
+            };
+
+            let mut indices = vec![0u8; area];
+            for j in 0..area {
+                indices[order(j)] = packet.g1()?;
+            }
+            let mut alphas: Vec<u8> = indices.iter().map(|index| if *index == 0 { 0 } else { 255 }).collect();
+            if flags & FLAG_ALPHA != 0 {
+                for j in 0..area {
+                    alphas[order(j)] = packet.g1()?;
+                }
+            }
+
+            let mut pixels = Vec::with_capacity(area * 4);
+            for (index, alpha) in indices.into_iter().zip(alphas) {
+                let Some(colour) = palette.get(index as usize) else {
+                    return error(format!("Sprite palette index {} exceeds the palette of {} colours.", index, colours));
+                };
+                pixels.extend_from_slice(&[(colour >> 16) as u8, (colour >> 8) as u8, *colour as u8, alpha]);
+            }
+            frames.push(Sprite {
+                offset_x: offsets_x[i],
+                offset_y: offsets_y[i],
+                width: widths[i],
+                height: heights[i],
+                pixels,
+            });
+        }
+        packet.seek_from_end(0)?;
+        Ok(SpriteSheet { width, height, frames })
+    }
+
+    /// Encodes the sheet, building a palette shared by every frame. Sheets holding more than
+    /// [MAX_PALETTE_COLOURS] distinct colours are quantized with a median cut. Pure black is
+    /// stored as `0x000001`, as the client does when decoding.
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if self.frames.len() > u16::MAX as usize {
+            return error(format!("Too many sprite frames ({}).", self.frames.len()));
+        }
+        if let Some(frame) = self.frames.iter().find(|frame| frame.pixels.len() != frame.width as usize * frame.height as usize * 4) {
+            return error(format!(
+                "Sprite frame of {}x{} holds {} bytes of pixels.",
+                frame.width,
+                frame.height,
+                frame.pixels.len()
+            ));
+        }
+
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for frame in &self.frames {
+            for pixel in frame.pixels.chunks_exact(4).filter(|pixel| pixel[3] != 0) {
+                *counts.entry(rgb(pixel)).or_default() += 1;
+            }
+        }
+        let palette = quantize(counts.into_iter().collect(), MAX_PALETTE_COLOURS);
+        let mut lookup: HashMap<u32, u8> = HashMap::new();
+
+        for frame in &self.frames {
+            let opaque = frame.pixels.chunks_exact(4).all(|pixel| pixel[3] == 0 || pixel[3] == 255);
+            packet.p1(if opaque { 0 } else { FLAG_ALPHA });
+            for pixel in frame.pixels.chunks_exact(4) {
+                if pixel[3] == 0 {
+                    packet.p1(0);
+                    continue;
+                }
+                let colour = rgb(pixel);
+                let index = *lookup.entry(colour).or_insert_with(|| nearest(&palette, colour) as u8 + 1);
+                packet.p1(index);
+            }
+            if !opaque {
+                frame.pixels.chunks_exact(4).for_each(|pixel| packet.p1(pixel[3]));
+            }
+        }
+
+        palette.iter().for_each(|colour| packet.p3((*colour).max(1)));
+        packet.p2(self.width);
+        packet.p2(self.height);
+        packet.p1(palette.len() as u8);
+        self.frames.iter().for_each(|frame| packet.p2(frame.offset_x));
+        self.frames.iter().for_each(|frame| packet.p2(frame.offset_y));
+        self.frames.iter().for_each(|frame| packet.p2(frame.width));
+        self.frames.iter().for_each(|frame| packet.p2(frame.height));
+        packet.p2(self.frames.len() as u16);
+        Ok(())
+    }
+}
+
+fn rgb(pixel: &[u8]) -> u32 {
+    (pixel[0] as u32) << 16 | (pixel[1] as u32) << 8 | pixel[2] as u32
+}
+
+fn channel(colour: u32, channel: usize) -> u32 {
+    (colour >> (16 - channel * 8)) & 0xFF
+}
+
+/// Reduces `colours`, each paired with the amount of pixels using it, to at most `max`
+/// colours. Boxes of colours are repeatedly split at the median of their widest channel, and
+/// each box is replaced by the weighted average of its colours.
+fn quantize(mut colours: Vec<(u32, usize)>, max: usize) -> Vec<u32> {
+    colours.sort_unstable();
+    if colours.len() <= max {
+        return colours.into_iter().map(|(colour, _)| colour).collect();
+    }
+
+    let range = |colours: &[(u32, usize)]| {
+        (0..3)
+            .map(|c| {
+                let values = colours.iter().map(|(colour, _)| channel(*colour, c));
+                let (min, max) = values.fold((255, 0), |(min, max), v| (v.min(min), v.max(max)));
+                (max - min, c)
+            })
+            .max()
+            .unwrap_or_default()
+    };
+
+    let mut boxes = vec![colours];
+    while boxes.len() < max {
+        let Some((index, (_, c))) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colours)| colours.len() > 1)
+            .map(|(index, colours)| (index, range(colours)))
+            .max_by_key(|(_, (range, _))| *range)
+        else {
+            break;
+        };
+        let mut colours = boxes.swap_remove(index);
+        colours.sort_unstable_by_key(|(colour, _)| channel(*colour, c));
+        let upper = colours.split_off(colours.len() / 2);
+        boxes.push(colours);
+        boxes.push(upper);
+    }
+
+    boxes
+        .into_iter()
+        .map(|colours| {
+            let total = colours.iter().map(|(_, count)| *count as u64).sum::<u64>().max(1);
+            (0..3).fold(0, |rgb, c| {
+                let sum = colours.iter().map(|(colour, count)| channel(*colour, c) as u64 * *count as u64).sum::<u64>();
+                rgb | ((sum / total) as u32) << (16 - c * 8)
+            })
+        })
+        .collect()
+}
+
+/// Returns the index of the palette colour closest to `colour`.
+fn nearest(palette: &[u32], colour: u32) -> usize {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, candidate)| {
+            (0..3)
+                .map(|c| {
+                    let delta = channel(**candidate, c) as i32 - channel(colour, c) as i32;
+                    delta * delta
+                })
+                .sum::<i32>()
+        })
+        .map_or(0, |(index, _)| index)
+}
@@ -0,0 +1,309 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::cmp::min;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// The size of a single sector within the data file, including its header.
+pub const SECTOR_SIZE: usize = 520;
+
+/// The size of a single entry within an index file.
+pub const INDEX_ENTRY_SIZE: usize = 6;
+
+const HEADER_SIZE: usize = 8;
+const EXTENDED_HEADER_SIZE: usize = 10;
+
+/// Groups with an id above this value use the extended 10-byte sector header.
+const MAX_SHORT_GROUP: u32 = 0xFFFF;
+
+/// A header preceding the data of every sector in the data file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SectorHeader {
+    group: u32,
+    chunk: u16,
+    next_sector: u32,
+    index: u8,
+}
+
+impl SectorHeader {
+    fn decode(packet: &mut Packet, extended: bool) -> Result<SectorHeader, PacketError> {
+        let group = if extended { packet.g4()? } else { packet.g2()? as u32 };
+        let chunk = packet.g2()?;
+        let next_sector = packet.g3()? as u32;
+        let index = packet.g1()?;
+        Ok(SectorHeader { group, chunk, next_sector, index })
+    }
+
+    fn encode(&self, packet: &mut Packet, extended: bool) {
+        if extended {
+            packet.p4(self.group);
+        } else {
+            packet.p2(self.group as u16);
+        }
+        packet.p2(self.chunk);
+        packet.p3(self.next_sector);
+        packet.p1(self.index);
+    }
+}
+
+/// Returns the header and data sizes of the sectors belonging to `group`.
+fn sector_layout(group: u32) -> (usize, usize) {
+    let header_size = if group > MAX_SHORT_GROUP { EXTENDED_HEADER_SIZE } else { HEADER_SIZE };
+    (header_size, SECTOR_SIZE - header_size)
+}
+
+/// An on-disk cache made up of a single `main_file_cache.dat2` data file holding chains of
+/// sectors, and one `main_file_cache.idx{n}` file per archive addressing the first sector and
+/// length of every group.
+#[derive(Debug)]
+pub struct FileStore {
+    root: PathBuf,
+    data: File,
+    indexes: BTreeMap<u8, File>,
+}
+
+impl FileStore {
+    /// Opens the cache within the directory `root`, creating the data file if it does not exist.
+    /// Every existing index file is opened alongside it.
+    pub fn open(root: impl AsRef<Path>) -> Result<FileStore, PacketError> {
+        let root = root.as_ref().to_path_buf();
+        let data = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(root.join("main_file_cache.dat2"))?;
+
+        let mut indexes = BTreeMap::new();
+        for index in 0..=u8::MAX {
+            let path = Self::index_path(&root, index);
+            if path.exists() {
+                indexes.insert(index, OpenOptions::new().read(true).write(true).open(path)?);
+            }
+        }
+
+        Ok(FileStore { root, data, indexes })
+    }
+
+    fn index_path(root: &Path, index: u8) -> PathBuf {
+        root.join(format!("main_file_cache.idx{}", index))
+    }
+
+    /// Returns the ids of every index file present in the cache.
+    pub fn indexes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.indexes.keys().copied()
+    }
+
+    /// Returns the amount of group entries the given index has room for, whether or not each
+    /// entry refers to an existing group.
+    pub fn group_count(&self, index: u8) -> Result<u32, PacketError> {
+        match self.indexes.get(&index) {
+            Some(file) => Ok((file.metadata()?.len() / INDEX_ENTRY_SIZE as u64) as u32),
+            None => error(format!("Index {} does not exist.", index)),
+        }
+    }
+
+    /// Reads the `(length, first_sector)` entry of a group, returning `None` if the entry lies
+    /// beyond the end of the index file or the group has no sectors.
+    fn entry(&mut self, index: u8, group: u32) -> Result<Option<(usize, u32)>, PacketError> {
+        let file = match self.indexes.get_mut(&index) {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+
+        let offset = group as u64 * INDEX_ENTRY_SIZE as u64;
+        if offset + INDEX_ENTRY_SIZE as u64 > file.metadata()?.len() {
+            return Ok(None);
+        }
+
+        let mut bytes = [0u8; INDEX_ENTRY_SIZE];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut bytes)?;
+
+        let mut packet = Packet::from(&bytes);
+        let len = packet.g3()?;
+        let sector = packet.g3()? as u32;
+        if sector == 0 {
+            return Ok(None);
+        }
+        Ok(Some((len, sector)))
+    }
+
+    fn sector_count(&self) -> Result<u32, PacketError> {
+        Ok((self.data.metadata()?.len().div_ceil(SECTOR_SIZE as u64)) as u32)
+    }
+
+    /// Returns `true` if the given group exists within the index.
+    pub fn exists(&mut self, index: u8, group: u32) -> Result<bool, PacketError> {
+        Ok(self.entry(index, group)?.is_some())
+    }
+
+    /// Reads a group by following its sector chain, verifying every sector header along the way.
+    pub fn read(&mut self, index: u8, group: u32) -> Result<Vec<u8>, PacketError> {
+        let (len, first_sector) = match self.entry(index, group)? {
+            Some(entry) => entry,
+            None => return error(format!("Group {} does not exist in index {}.", group, index)),
+        };
+
+        let (header_size, data_size) = sector_layout(group);
+        let sector_count = self.sector_count()?;
+        let mut data = Vec::with_capacity(len);
+        let mut sector = first_sector;
+        let mut chunk = 0u16;
+        let mut buffer = [0u8; SECTOR_SIZE];
+
+        while data.len() < len {
+            if sector == 0 || sector >= sector_count {
+                return error(format!(
+                    "Sector chain for group {} in index {} points to invalid sector {}.",
+                    group, index, sector
+                ));
+            }
+
+            let remaining = min(len - data.len(), data_size);
+            let sector_len = header_size + remaining;
+            self.data.seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE as u64))?;
+            self.data.read_exact(&mut buffer[..sector_len])?;
+
+            let mut packet = Packet::from(&buffer[..sector_len]);
+            let header = SectorHeader::decode(&mut packet, header_size == EXTENDED_HEADER_SIZE)?;
+            if header.group != group || header.chunk != chunk || header.index != index {
+                return error(format!(
+                    "Sector {} header mismatch. Expected ({}, {}, {}), found ({}, {}, {}).",
+                    sector, index, group, chunk, header.index, header.group, header.chunk
+                ));
+            }
+
+            data.extend_from_slice(packet.slice_remaining());
+            sector = header.next_sector;
+            chunk = chunk.wrapping_add(1);
+        }
+
+        Ok(data)
+    }
+
+    /// Walks the sector chain of every group in the given index, returning the ids of the
+    /// groups whose chain is broken or whose headers do not match.
+    pub fn verify(&mut self, index: u8) -> Result<Vec<u32>, PacketError> {
+        let mut corrupt = Vec::new();
+        for group in 0..self.group_count(index)? {
+            if self.exists(index, group)? && self.read(index, group).is_err() {
+                corrupt.push(group);
+            }
+        }
+        Ok(corrupt)
+    }
+
+    /// Collects the sectors currently used by a group, stopping at the first sector that does
+    /// not belong to it.
+    fn existing_sectors(&mut self, index: u8, group: u32) -> Result<Vec<u32>, PacketError> {
+        let mut sectors = Vec::new();
+        let (len, mut sector) = match self.entry(index, group)? {
+            Some(entry) => entry,
+            None => return Ok(sectors),
+        };
+
+        let (header_size, data_size) = sector_layout(group);
+        let sector_count = self.sector_count()?;
+        let mut header = [0u8; EXTENDED_HEADER_SIZE];
+        let chunks = len.div_ceil(data_size);
+
+        while sector != 0 && sector < sector_count && sectors.len() < chunks {
+            self.data.seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE as u64))?;
+            if self.data.read_exact(&mut header[..header_size]).is_err() {
+                break;
+            }
+
+            let mut packet = Packet::from(&header[..header_size]);
+            let decoded = SectorHeader::decode(&mut packet, header_size == EXTENDED_HEADER_SIZE)?;
+            if decoded.group != group || decoded.chunk as usize != sectors.len() || decoded.index != index {
+                break;
+            }
+            if sectors.contains(&sector) {
+                break;
+            }
+            sectors.push(sector);
+            sector = decoded.next_sector;
+        }
+        Ok(sectors)
+    }
+
+    /// Writes a group, reusing the sectors of any existing chain for the group and allocating
+    /// new sectors at the end of the data file once those run out.
+    pub fn write(&mut self, index: u8, group: u32, data: &[u8]) -> Result<(), PacketError> {
+        if data.len() > 0xFFFFFF {
+            return error(format!("Group {} is too large to store ({} bytes).", group, data.len()));
+        }
+
+        let (header_size, data_size) = sector_layout(group);
+        let mut sectors = self.existing_sectors(index, group)?;
+        let chunks = data.len().div_ceil(data_size).max(1);
+        sectors.truncate(chunks);
+
+        let mut next_free = self.sector_count()?.max(1);
+        while sectors.len() < chunks {
+            sectors.push(next_free);
+            next_free += 1;
+        }
+
+        let parts: Vec<&[u8]> = if data.is_empty() { vec![&[]] } else { data.chunks(data_size).collect() };
+        for (chunk, bytes) in parts.into_iter().enumerate() {
+            let header = SectorHeader {
+                group,
+                chunk: chunk as u16,
+                next_sector: sectors.get(chunk + 1).copied().unwrap_or(0),
+                index,
+            };
+
+            let mut packet = Packet::new(header_size + bytes.len());
+            header.encode(&mut packet, header_size == EXTENDED_HEADER_SIZE);
+            packet.write_all(bytes)?;
+            packet.set_pos(0)?;
+
+            self.data.seek(SeekFrom::Start(sectors[chunk] as u64 * SECTOR_SIZE as u64))?;
+            self.data.write_all(packet.as_ref())?;
+        }
+
+        let mut entry = Packet::new(INDEX_ENTRY_SIZE);
+        entry.p3(data.len() as u32);
+        entry.p3(sectors[0]);
+        entry.set_pos(0)?;
+        self.write_entry(index, group, entry.as_ref())
+    }
+
+    fn write_entry(&mut self, index: u8, group: u32, entry: &[u8]) -> Result<(), PacketError> {
+        if !self.indexes.contains_key(&index) {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(Self::index_path(&self.root, index))?;
+            self.indexes.insert(index, file);
+        }
+
+        let file = self.indexes.get_mut(&index).unwrap();
+        file.seek(SeekFrom::Start(group as u64 * INDEX_ENTRY_SIZE as u64))?;
+        file.write_all(entry)?;
+        Ok(())
+    }
+
+    /// Removes a group from the index. The sectors it occupied are left in place.
+    pub fn remove(&mut self, index: u8, group: u32) -> Result<(), PacketError> {
+        if self.exists(index, group)? {
+            self.write_entry(index, group, &[0; INDEX_ENTRY_SIZE])?;
+        }
+        Ok(())
+    }
+
+    /// Flushes any buffered writes to the data and index files.
+    pub fn flush(&mut self) -> Result<(), PacketError> {
+        self.data.flush()?;
+        for file in self.indexes.values_mut() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
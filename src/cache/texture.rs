@@ -0,0 +1,78 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// A texture, stored in the textures archive and drawn from one or more
+/// [sprite sheets](crate::cache::sprite::SpriteSheet).
+///
+/// - `g2` average colour, `g1` opaque
+/// - `g1` count, then `g2` sprite id per sprite
+/// - `g1` sprite type and then `g1` sprite transform per sprite after the first, each as a
+///   separate list
+/// - `g4` colour per sprite
+/// - `g1` animation direction, `g1` animation speed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextureType {
+    /// The HSL colour drawn in place of the texture on low detail.
+    pub average_colour: u16,
+    pub opaque: bool,
+    pub sprites: Vec<u16>,
+    /// How each sprite after the first is combined with the previous ones.
+    pub sprite_types: Vec<u8>,
+    pub sprite_transforms: Vec<u8>,
+    /// The colour each sprite is tinted with.
+    pub colours: Vec<i32>,
+    pub animation_direction: u8,
+    pub animation_speed: u8,
+}
+
+impl TextureType {
+    pub fn decode(packet: &mut Packet) -> Result<TextureType, PacketError> {
+        let average_colour = packet.g2()?;
+        let opaque = packet.g1()? != 0;
+        let count = packet.g1()? as usize;
+        let sprites = (0..count).map(|_| packet.g2()).collect::<Result<_, _>>()?;
+        let extra = count.saturating_sub(1);
+        let sprite_types = (0..extra).map(|_| packet.g1()).collect::<Result<_, _>>()?;
+        let sprite_transforms = (0..extra).map(|_| packet.g1()).collect::<Result<_, _>>()?;
+        let colours = (0..count).map(|_| packet.g4s()).collect::<Result<_, _>>()?;
+        Ok(TextureType {
+            average_colour,
+            opaque,
+            sprites,
+            sprite_types,
+            sprite_transforms,
+            colours,
+            animation_direction: packet.g1()?,
+            animation_speed: packet.g1()?,
+        })
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        let count = self.sprites.len();
+        let extra = count.saturating_sub(1);
+        if count > u8::MAX as usize
+            || self.sprite_types.len() != extra
+            || self.sprite_transforms.len() != extra
+            || self.colours.len() != count
+        {
+            return error(format!(
+                "Texture of {} sprites has {} types, {} transforms and {} colours.",
+                count,
+                self.sprite_types.len(),
+                self.sprite_transforms.len(),
+                self.colours.len()
+            ));
+        }
+
+        packet.p2(self.average_colour);
+        packet.p1(self.opaque as u8);
+        packet.p1(count as u8);
+        self.sprites.iter().for_each(|sprite| packet.p2(*sprite));
+        self.sprite_types.iter().for_each(|kind| packet.p1(*kind));
+        self.sprite_transforms.iter().for_each(|transform| packet.p1(*transform));
+        self.colours.iter().for_each(|colour| packet.p4s(*colour));
+        packet.p1(self.animation_direction);
+        packet.p1(self.animation_speed);
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+use crate::cache::container::Container;
+use crate::cache::reference::ReferenceTable;
+use crate::cache::store::FileStore;
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use encoding_rs::WINDOWS_1252;
+use num_bigint::BigInt;
+use std::io::Write;
+use std::ops::Range;
+
+/// The length of a whirlpool digest in bytes.
+pub const WHIRLPOOL_LEN: usize = 64;
+
+/// Computes the CRC-32 checksum of `data`, as used for cache checksums and the login CRC list.
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32fast::hash(data)
+}
+
+/// Hashes an archive or group name the same way the client does: the name is lowercased,
+/// encoded as cp1252, and hashed in the style of Java's `String.hashCode`.
+pub fn name_hash(name: &str) -> i32 {
+    let lower = name.to_lowercase();
+    let (bytes, _, _) = WINDOWS_1252.encode(&lower);
+    bytes
+        .iter()
+        .fold(0i32, |hash, byte| hash.wrapping_mul(31).wrapping_add(*byte as i32))
+}
+
+/// Computes the whirlpool digest of `data`.
+pub fn whirlpool(data: &[u8]) -> [u8; WHIRLPOOL_LEN] {
+    let mut hash = [0u64; 8];
+
+    // Pad with a single set bit, then zeroes until 32 bytes remain in the final block, which are
+    // filled with the length of the data in bits.
+    let bit_len = (data.len() as u128) * 8;
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 32 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&[0u8; 16]);
+    padded.extend_from_slice(&bit_len.to_be_bytes());
+
+    for block in padded.chunks_exact(64) {
+        let mut words = [0u64; 8];
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(8)) {
+            *word = u64::from_be_bytes(bytes.try_into().unwrap());
+        }
+        whirlpool_block(&mut hash, &words);
+    }
+
+    let mut digest = [0u8; WHIRLPOOL_LEN];
+    for (bytes, word) in digest.chunks_exact_mut(8).zip(hash) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+const WHIRLPOOL_ROUNDS: usize = 10;
+
+/// The whirlpool S-box, built from the E, E^-1 and R mini-boxes described in the specification.
+const WHIRLPOOL_SBOX: [u8; 256] = {
+    const E: [u8; 16] = [0x1, 0xB, 0x9, 0xC, 0xD, 0x6, 0xF, 0x3, 0xE, 0x8, 0x7, 0x4, 0xA, 0x2, 0x5, 0x0];
+    const R: [u8; 16] = [0x7, 0xC, 0xB, 0xD, 0xE, 0x4, 0x9, 0xF, 0x6, 0x3, 0x8, 0xA, 0x2, 0x5, 0x1, 0x0];
+
+    let mut inverse = [0u8; 16];
+    let mut i = 0;
+    while i < 16 {
+        inverse[E[i] as usize] = i as u8;
+        i += 1;
+    }
+
+    let mut sbox = [0u8; 256];
+    let mut x = 0;
+    while x < 256 {
+        let a = E[x >> 4];
+        let b = inverse[x & 0xF];
+        let r = R[(a ^ b) as usize];
+        sbox[x] = (E[(a ^ r) as usize] << 4) | inverse[(b ^ r) as usize];
+        x += 1;
+    }
+    sbox
+};
+
+/// The circulant table lookups, where `WHIRLPOOL_TABLES[t][x]` is the row for byte `x` rotated
+/// right by `t` bytes.
+const WHIRLPOOL_TABLES: [[u64; 256]; 8] = {
+    const fn double(value: u64) -> u64 {
+        let value = value << 1;
+        if value >= 0x100 { value ^ 0x11D } else { value }
+    }
+
+    let mut tables = [[0u64; 256]; 8];
+    let mut x = 0;
+    while x < 256 {
+        let v1 = WHIRLPOOL_SBOX[x] as u64;
+        let v2 = double(v1);
+        let v4 = double(v2);
+        let v5 = v4 ^ v1;
+        let v8 = double(v4);
+        let v9 = v8 ^ v1;
+        let row = (v1 << 56) | (v1 << 48) | (v4 << 40) | (v1 << 32) | (v8 << 24) | (v5 << 16) | (v2 << 8) | v9;
+
+        let mut t = 0;
+        while t < 8 {
+            tables[t][x] = row.rotate_right(8 * t as u32);
+            t += 1;
+        }
+        x += 1;
+    }
+    tables
+};
+
+const WHIRLPOOL_ROUND_CONSTANTS: [u64; WHIRLPOOL_ROUNDS] = {
+    let mut constants = [0u64; WHIRLPOOL_ROUNDS];
+    let mut r = 0;
+    while r < WHIRLPOOL_ROUNDS {
+        let mut t = 0;
+        while t < 8 {
+            constants[r] ^= WHIRLPOOL_TABLES[t][8 * r + t] & (0xFF00_0000_0000_0000 >> (8 * t));
+            t += 1;
+        }
+        r += 1;
+    }
+    constants
+};
+
+fn whirlpool_round(input: &[u64; 8], key: &[u64; 8]) -> [u64; 8] {
+    let mut output = *key;
+    for (i, word) in output.iter_mut().enumerate() {
+        for (t, table) in WHIRLPOOL_TABLES.iter().enumerate() {
+            *word ^= table[((input[(i + 8 - t) % 8] >> (56 - 8 * t)) & 0xFF) as usize];
+        }
+    }
+    output
+}
+
+fn whirlpool_block(hash: &mut [u64; 8], block: &[u64; 8]) {
+    let mut key = *hash;
+    let mut state = [0u64; 8];
+    for i in 0..8 {
+        state[i] = block[i] ^ key[i];
+    }
+
+    for constant in WHIRLPOOL_ROUND_CONSTANTS {
+        let mut round_key = [0u64; 8];
+        round_key[0] = constant;
+        key = whirlpool_round(&key, &round_key);
+        state = whirlpool_round(&state, &key);
+    }
+
+    for i in 0..8 {
+        hash[i] ^= state[i] ^ block[i];
+    }
+}
+
+impl Packet {
+    /// Computes the CRC-32 checksum of the given range of the remaining readable bytes, returning
+    /// `None` if the range is out of bounds.
+    pub fn crc32(&self, range: Range<usize>) -> Option<u32> {
+        self.get(range).map(crc32)
+    }
+
+    /// Computes the whirlpool digest of the given range of the remaining readable bytes,
+    /// returning `None` if the range is out of bounds.
+    pub fn whirlpool(&self, range: Range<usize>) -> Option<[u8; WHIRLPOOL_LEN]> {
+        self.get(range).map(whirlpool)
+    }
+}
+
+/// The layout of a [ChecksumTable], which grew over time as revisions added fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChecksumTableFormat {
+    /// A CRC per archive.
+    Original,
+    /// A CRC and reference table version per archive.
+    Versioned,
+    /// Adds an archive count, a whirlpool digest per archive and a signed digest of the table.
+    Digests,
+    /// Adds the group count and total uncompressed length per archive.
+    Lengths,
+}
+
+/// The checksum of a single archive's reference table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumEntry {
+    pub crc: u32,
+    pub version: u32,
+    pub groups: u32,
+    pub length: u32,
+    pub digest: [u8; WHIRLPOOL_LEN],
+}
+
+impl Default for ChecksumEntry {
+    fn default() -> Self {
+        Self {
+            crc: 0,
+            version: 0,
+            groups: 0,
+            length: 0,
+            digest: [0; WHIRLPOOL_LEN],
+        }
+    }
+}
+
+/// The table of reference table checksums served to the client as group `255` of archive `255`,
+/// allowing it to determine which archives are out of date.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChecksumTable {
+    pub entries: Vec<ChecksumEntry>,
+}
+
+impl ChecksumTable {
+    /// Builds the checksum table from the reference tables stored in archive `255` of the
+    /// store. Archives without a reference table are given an empty entry.
+    pub fn from_store(store: &mut FileStore) -> Result<ChecksumTable, PacketError> {
+        let count = store.group_count(255)?;
+        let mut entries = Vec::with_capacity(count as usize);
+        for archive in 0..count {
+            if !store.exists(255, archive)? {
+                entries.push(ChecksumEntry::default());
+                continue;
+            }
+
+            let data = store.read(255, archive)?;
+            let mut packet = Packet::from(data.as_slice());
+            let container = Container::decode(&mut packet, None)?;
+            let table = ReferenceTable::decode(&mut Packet::from(container.data))?;
+            entries.push(ChecksumEntry {
+                crc: crc32(&data),
+                version: table.version,
+                groups: table.groups.len() as u32,
+                // The client sums the lengths as an int, so larger caches wrap around.
+                length: table.groups.values().fold(0u32, |sum, group| sum.wrapping_add(group.uncompressed_length)),
+                digest: whirlpool(&data),
+            });
+        }
+        Ok(ChecksumTable { entries })
+    }
+
+    /// Encodes the table in the given format. From [Digests](ChecksumTableFormat::Digests)
+    /// onwards the table ends with a block holding a whirlpool digest of the table, encrypted
+    /// with the given RSA `(exponent, modulus)` if one is provided.
+    pub fn encode(
+        &self,
+        format: ChecksumTableFormat,
+        key: Option<(&BigInt, &BigInt)>,
+    ) -> Result<Packet, PacketError> {
+        let mut packet = Packet::empty();
+        if format >= ChecksumTableFormat::Digests {
+            packet.p1(self.entries.len() as u8);
+        }
+        for entry in &self.entries {
+            packet.p4(entry.crc);
+            if format >= ChecksumTableFormat::Versioned {
+                packet.p4(entry.version);
+            }
+            if format >= ChecksumTableFormat::Lengths {
+                packet.p4(entry.groups);
+                packet.p4(entry.length);
+            }
+            if format >= ChecksumTableFormat::Digests {
+                packet.write_all(&entry.digest)?;
+            }
+        }
+
+        if format >= ChecksumTableFormat::Digests {
+            let mut block = vec![0u8];
+            block.extend_from_slice(&packet.with_pos(0, |packet| Ok(whirlpool(packet.as_ref())))?);
+            if let Some((exponent, modulus)) = key {
+                block = BigInt::from_signed_bytes_be(&block)
+                    .modpow(exponent, modulus)
+                    .to_signed_bytes_be();
+            }
+            packet.write_all(&block)?;
+        }
+
+        packet.set_pos(0)?;
+        Ok(packet)
+    }
+
+    /// Decodes a table in the given format, ignoring the trailing signed digest if present.
+    pub fn decode(packet: &mut Packet, format: ChecksumTableFormat) -> Result<ChecksumTable, PacketError> {
+        let entry_len = match format {
+            ChecksumTableFormat::Original => 4,
+            ChecksumTableFormat::Versioned => 8,
+            ChecksumTableFormat::Digests => 8 + WHIRLPOOL_LEN,
+            ChecksumTableFormat::Lengths => 16 + WHIRLPOOL_LEN,
+        };
+        let count = if format >= ChecksumTableFormat::Digests {
+            packet.g1()? as usize
+        } else {
+            packet.available_count() / entry_len
+        };
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut entry = ChecksumEntry { crc: packet.g4()?, ..Default::default() };
+            if format >= ChecksumTableFormat::Versioned {
+                entry.version = packet.g4()?;
+            }
+            if format >= ChecksumTableFormat::Lengths {
+                entry.groups = packet.g4()?;
+                entry.length = packet.g4()?;
+            }
+            if format >= ChecksumTableFormat::Digests {
+                if !packet.has_available(WHIRLPOOL_LEN) {
+                    return error("Not enough data for archive digest.".to_string());
+                }
+                entry.digest.copy_from_slice(&packet.gdata(WHIRLPOOL_LEN));
+            }
+            entries.push(entry);
+        }
+        Ok(ChecksumTable { entries })
+    }
+}
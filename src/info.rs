@@ -0,0 +1,90 @@
+pub mod mask;
+pub mod npc;
+pub mod player;
+
+use crate::packet::bits::BitReader;
+use crate::packet::error::PacketError;
+
+/// The movement applied to an entity within the bit-access section of an info packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Movement {
+    /// The entity did not move, but an update mask block follows for it.
+    None,
+    /// The entity took a single step in the given `direction` (0-7).
+    Walk { direction: u8 },
+    /// The entity took two steps, `first` followed by `second`.
+    Run { first: u8, second: u8 },
+    /// The entity was placed at a new local position, optionally clearing its waypoints.
+    Teleport {
+        plane: u8,
+        x: u8,
+        y: u8,
+        clear_waypoints: bool,
+    },
+    /// The entity is no longer tracked by the client.
+    Remove,
+}
+
+/// An update to an entity the client was already tracking prior to the packet being read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityUpdate {
+    pub movement: Movement,
+    pub update_required: bool,
+}
+
+/// Identifies which entity a decoded [UpdateMask](mask::UpdateMask) belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskTarget {
+    /// The player the packet is being sent to.
+    LocalPlayer,
+    /// An entity at the given slot within the list of already tracked entities.
+    Tracked(usize),
+    /// An entity added by this packet, identified by its server index.
+    Added(usize),
+}
+
+/// Reads a 5-bit signed coordinate delta as used when adding entities to the local list.
+fn read_delta(reader: &mut BitReader) -> Result<i32, PacketError> {
+    let value = reader.read_bits(5)? as i32;
+    Ok(if value > 15 { value - 32 } else { value })
+}
+
+/// Reads the movement of an already tracked entity following its 2-bit movement type. A type of
+/// `3` is treated as a removal, as is the case for every entity other than the local player.
+fn read_tracked_update(reader: &mut BitReader) -> Result<EntityUpdate, PacketError> {
+    let kind = reader.read_bits(2)?;
+    read_movement(reader, kind)
+}
+
+fn read_movement(reader: &mut BitReader, kind: usize) -> Result<EntityUpdate, PacketError> {
+    match kind {
+        0 => Ok(EntityUpdate { movement: Movement::None, update_required: true }),
+        1 => {
+            let direction = reader.read_bits(3)? as u8;
+            let update_required = reader.read_bits(1)? == 1;
+            Ok(EntityUpdate { movement: Movement::Walk { direction }, update_required })
+        }
+        2 => {
+            let first = reader.read_bits(3)? as u8;
+            let second = reader.read_bits(3)? as u8;
+            let update_required = reader.read_bits(1)? == 1;
+            Ok(EntityUpdate { movement: Movement::Run { first, second }, update_required })
+        }
+        _ => Ok(EntityUpdate { movement: Movement::Remove, update_required: false }),
+    }
+}
+
+/// Reads the tracked entity list: an 8-bit count followed by a 1-bit "changed" flag per entity
+/// and, when set, the entity's movement.
+fn read_tracked(reader: &mut BitReader) -> Result<Vec<Option<EntityUpdate>>, PacketError> {
+    let count = reader.read_bits(8)?;
+    let mut tracked = Vec::with_capacity(count);
+    for _ in 0..count {
+        if reader.read_bits(1)? == 0 {
+            tracked.push(None);
+        } else {
+            tracked.push(Some(read_tracked_update(reader)?));
+        }
+    }
+    Ok(tracked)
+}
@@ -0,0 +1,112 @@
+use crate::info::MaskTarget;
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// Describes how many bytes a single mask block occupies.
+#[derive(Debug, Clone, Copy)]
+pub enum BlockSize {
+    /// The block always occupies the given amount of bytes.
+    Fixed(usize),
+    /// The block length is determined from its contents. The function is handed the remaining
+    /// bytes of the packet starting at the block and returns the total length of the block, or
+    /// `None` if the block is malformed.
+    Variable(fn(&[u8]) -> Option<usize>),
+}
+
+impl BlockSize {
+    /// A block holding a single null-terminated string, such as forced chat.
+    pub const JSTR: BlockSize = BlockSize::Variable(jstr_len);
+}
+
+fn jstr_len(bytes: &[u8]) -> Option<usize> {
+    memchr::memchr(0, bytes).map(|null_pos| null_pos + 1)
+}
+
+/// A raw mask block read for a single flag. The contents are left undecoded as they differ from
+/// one revision to the next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskBlock {
+    pub flag: u32,
+    pub data: Vec<u8>,
+}
+
+/// The set of mask blocks decoded for a single entity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateMask {
+    pub target: MaskTarget,
+    pub flags: u32,
+    pub blocks: Vec<MaskBlock>,
+}
+
+/// Describes the mask block layout of a given revision: the order in which blocks are written,
+/// their sizes, and the flag (if any) signalling that a second flag byte follows.
+///
+/// ```
+/// use rs2io::info::mask::{BlockSize, MaskLayout};
+///
+/// let layout = MaskLayout::new()
+///     .extended_flag(0x40)
+///     .block(0x8, BlockSize::Fixed(3))
+///     .block(0x4, BlockSize::JSTR);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MaskLayout {
+    extended_flag: Option<u32>,
+    blocks: Vec<(u32, BlockSize)>,
+}
+
+impl MaskLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the flag which, when present in the first flag byte, indicates that a second flag
+    /// byte follows and should be shifted into the upper 8 bits of the flags.
+    pub fn extended_flag(mut self, flag: u32) -> Self {
+        self.extended_flag = Some(flag);
+        self
+    }
+
+    /// Appends a block to the layout. Blocks are read in the order they are added.
+    pub fn block(mut self, flag: u32, size: BlockSize) -> Self {
+        self.blocks.push((flag, size));
+        self
+    }
+
+    /// Reads the flags of a single entity followed by every block present in those flags.
+    pub fn decode(&self, target: MaskTarget, packet: &mut Packet) -> Result<UpdateMask, PacketError> {
+        let mut flags = packet.g1()? as u32;
+        if let Some(extended) = self.extended_flag {
+            if flags & extended != 0 {
+                flags |= (packet.g1()? as u32) << 8;
+            }
+        }
+
+        let mut blocks = Vec::new();
+        for (flag, size) in &self.blocks {
+            if flags & flag == 0 {
+                continue;
+            }
+
+            let len = match size {
+                BlockSize::Fixed(len) => *len,
+                BlockSize::Variable(len_of) => match len_of(packet.slice_remaining()) {
+                    Some(len) => len,
+                    None => return error(format!("Malformed variable mask block for flag {:#x}.", flag)),
+                },
+            };
+
+            if !packet.has_available(len) {
+                return error(format!(
+                    "Not enough data for mask block {:#x}. Needed {}, have {}.",
+                    flag,
+                    len,
+                    packet.available_count()
+                ));
+            }
+            blocks.push(MaskBlock { flag: *flag, data: packet.gdata(len) });
+        }
+
+        Ok(UpdateMask { target, flags, blocks })
+    }
+}
@@ -0,0 +1,77 @@
+use crate::info::mask::{MaskLayout, UpdateMask};
+use crate::info::{read_delta, read_tracked, EntityUpdate, MaskTarget};
+use crate::packet::bits::BitReader;
+use crate::packet::bytes::Packet;
+use crate::packet::error::PacketError;
+
+/// The index written in place of an npc index to terminate the list of added npcs.
+const END_OF_ADDITIONS: usize = 16383;
+
+/// An npc added to the local npc list by an npc info packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddedNpc {
+    pub index: usize,
+    pub id: usize,
+    pub dx: i32,
+    pub dy: i32,
+    pub discard_walk_queue: bool,
+    pub update_required: bool,
+}
+
+/// A decoded npc info packet, as seen by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NpcInfo {
+    /// One entry per npc the client was tracking, `None` for those that did not change.
+    pub tracked: Vec<Option<EntityUpdate>>,
+    pub added: Vec<AddedNpc>,
+    /// Mask blocks in the order they were written: tracked npcs followed by added npcs.
+    pub masks: Vec<UpdateMask>,
+}
+
+impl NpcInfo {
+    /// Decodes an npc info payload starting at the packet's current position. The bit-access
+    /// section is read first, after which the position is advanced to the next whole byte and
+    /// the mask blocks are read using the given `layout`.
+    pub fn decode(packet: &mut Packet, layout: &MaskLayout) -> Result<NpcInfo, PacketError> {
+        let (tracked, added, bits_read) = {
+            let mut reader = BitReader::from(&*packet);
+            let tracked = read_tracked(&mut reader)?;
+            let added = read_added(&mut reader)?;
+            (tracked, added, reader.get_bit_position())
+        };
+        packet.skip(bits_read.div_ceil(8));
+
+        let mut masks = Vec::new();
+        for (slot, update) in tracked.iter().enumerate() {
+            if update.is_some_and(|update| update.update_required) {
+                masks.push(layout.decode(MaskTarget::Tracked(slot), packet)?);
+            }
+        }
+        for npc in &added {
+            if npc.update_required {
+                masks.push(layout.decode(MaskTarget::Added(npc.index), packet)?);
+            }
+        }
+
+        Ok(NpcInfo { tracked, added, masks })
+    }
+}
+
+fn read_added(reader: &mut BitReader) -> Result<Vec<AddedNpc>, PacketError> {
+    let mut added = Vec::new();
+    // The terminator may be followed by as little as a single mask byte, so only the index
+    // itself needs to fit.
+    while reader.has_bits_available(14) {
+        let index = reader.read_bits(14)?;
+        if index == END_OF_ADDITIONS {
+            break;
+        }
+        let dy = read_delta(reader)?;
+        let dx = read_delta(reader)?;
+        let discard_walk_queue = reader.read_bits(1)? == 1;
+        let id = reader.read_bits(12)?;
+        let update_required = reader.read_bits(1)? == 1;
+        added.push(AddedNpc { index, id, dx, dy, discard_walk_queue, update_required });
+    }
+    Ok(added)
+}
@@ -0,0 +1,103 @@
+use crate::info::mask::{MaskLayout, UpdateMask};
+use crate::info::{read_delta, read_movement, read_tracked, EntityUpdate, MaskTarget, Movement};
+use crate::packet::bits::BitReader;
+use crate::packet::bytes::Packet;
+use crate::packet::error::PacketError;
+
+/// The index written in place of a player index to terminate the list of added players.
+const END_OF_ADDITIONS: usize = 2047;
+
+/// A player added to the local player list by a player info packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddedPlayer {
+    pub index: usize,
+    pub dx: i32,
+    pub dy: i32,
+    pub discard_walk_queue: bool,
+    pub update_required: bool,
+}
+
+/// A decoded player info packet, as seen by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerInfo {
+    /// The local player's update, or `None` if the local player did not change.
+    pub local: Option<EntityUpdate>,
+    /// One entry per player the client was tracking, `None` for those that did not change.
+    pub tracked: Vec<Option<EntityUpdate>>,
+    pub added: Vec<AddedPlayer>,
+    /// Mask blocks in the order they were written: local player, tracked players, then added.
+    pub masks: Vec<UpdateMask>,
+}
+
+impl PlayerInfo {
+    /// Decodes a player info payload starting at the packet's current position. The bit-access
+    /// section is read first, after which the position is advanced to the next whole byte and
+    /// the mask blocks are read using the given `layout`.
+    pub fn decode(packet: &mut Packet, layout: &MaskLayout) -> Result<PlayerInfo, PacketError> {
+        let (local, tracked, added, bits_read) = {
+            let mut reader = BitReader::from(&*packet);
+            let local = read_local(&mut reader)?;
+            let tracked = read_tracked(&mut reader)?;
+            let added = read_added(&mut reader)?;
+            (local, tracked, added, reader.get_bit_position())
+        };
+        packet.skip(bits_read.div_ceil(8));
+
+        let mut masks = Vec::new();
+        if local.is_some_and(|update| update.update_required) {
+            masks.push(layout.decode(MaskTarget::LocalPlayer, packet)?);
+        }
+        for (slot, update) in tracked.iter().enumerate() {
+            if update.is_some_and(|update| update.update_required) {
+                masks.push(layout.decode(MaskTarget::Tracked(slot), packet)?);
+            }
+        }
+        for player in &added {
+            if player.update_required {
+                masks.push(layout.decode(MaskTarget::Added(player.index), packet)?);
+            }
+        }
+
+        Ok(PlayerInfo { local, tracked, added, masks })
+    }
+}
+
+fn read_local(reader: &mut BitReader) -> Result<Option<EntityUpdate>, PacketError> {
+    if reader.read_bits(1)? == 0 {
+        return Ok(None);
+    }
+
+    // The local player is the only entity that can teleport through movement type 3.
+    let kind = reader.read_bits(2)?;
+    if kind != 3 {
+        return read_movement(reader, kind).map(Some);
+    }
+
+    let plane = reader.read_bits(2)? as u8;
+    let clear_waypoints = reader.read_bits(1)? == 1;
+    let update_required = reader.read_bits(1)? == 1;
+    let y = reader.read_bits(7)? as u8;
+    let x = reader.read_bits(7)? as u8;
+    Ok(Some(EntityUpdate {
+        movement: Movement::Teleport { plane, x, y, clear_waypoints },
+        update_required,
+    }))
+}
+
+fn read_added(reader: &mut BitReader) -> Result<Vec<AddedPlayer>, PacketError> {
+    let mut added = Vec::new();
+    // The terminator may be followed by as little as a single mask byte, so only the index
+    // itself needs to fit.
+    while reader.has_bits_available(11) {
+        let index = reader.read_bits(11)?;
+        if index == END_OF_ADDITIONS {
+            break;
+        }
+        let update_required = reader.read_bits(1)? == 1;
+        let discard_walk_queue = reader.read_bits(1)? == 1;
+        let dy = read_delta(reader)?;
+        let dx = read_delta(reader)?;
+        added.push(AddedPlayer { index, dx, dy, discard_walk_queue, update_required });
+    }
+    Ok(added)
+}
@@ -0,0 +1,189 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::io::Write;
+
+/// The size of a single block of a response, including the continuation marker.
+pub const BLOCK_SIZE: usize = 512;
+
+/// The byte starting every block of a response after the first.
+pub const BLOCK_MARKER: u8 = 0xFF;
+
+/// The length of every request sent by the client.
+pub const REQUEST_LEN: usize = 4;
+
+/// The flag set on the compression byte of responses to prefetch requests.
+const PREFETCH_FLAG: u8 = 0x80;
+
+/// A message sent by the client over an established JS5 connection. Every message is exactly
+/// [REQUEST_LEN] bytes long: an opcode followed by three bytes of payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Js5Request {
+    /// A request for a group. Prefetch requests are served after every urgent request.
+    Group { prefetch: bool, archive: u8, group: u16 },
+    /// The client has logged into the game, making its requests urgent.
+    LoggedIn,
+    /// The client has logged out of the game.
+    LoggedOut,
+    /// Sets the key every subsequent response byte is XORed with.
+    Rekey { key: u8 },
+    /// Sent once the client has initialized the connection.
+    Connected { value: u32 },
+    /// The client is closing the connection.
+    Disconnect,
+}
+
+impl Js5Request {
+    /// Decodes a single request, returning `None` without consuming any bytes if a complete
+    /// request is not yet available.
+    pub fn decode(packet: &mut Packet) -> Result<Option<Js5Request>, PacketError> {
+        if !packet.has_available(REQUEST_LEN) {
+            return Ok(None);
+        }
+
+        let opcode = packet.g1()?;
+        let payload = packet.g3()? as u32;
+        let request = match opcode {
+            0 | 1 => Js5Request::Group {
+                prefetch: opcode == 0,
+                archive: (payload >> 16) as u8,
+                group: payload as u16,
+            },
+            2 => Js5Request::LoggedIn,
+            3 => Js5Request::LoggedOut,
+            4 => Js5Request::Rekey { key: (payload >> 16) as u8 },
+            6 => Js5Request::Connected { value: payload },
+            7 => Js5Request::Disconnect,
+            _ => return error(format!("Unknown js5 request opcode {}.", opcode)),
+        };
+        Ok(Some(request))
+    }
+
+    /// Writes this request into the packet.
+    pub fn encode(&self, packet: &mut Packet) {
+        match *self {
+            Js5Request::Group { prefetch, archive, group } => {
+                packet.p1(if prefetch { 0 } else { 1 });
+                packet.p1(archive);
+                packet.p2(group);
+            }
+            Js5Request::LoggedIn => {
+                packet.p1(2);
+                packet.p3(0);
+            }
+            Js5Request::LoggedOut => {
+                packet.p1(3);
+                packet.p3(0);
+            }
+            Js5Request::Rekey { key } => {
+                packet.p1(4);
+                packet.p1(key);
+                packet.p2(0);
+            }
+            Js5Request::Connected { value } => {
+                packet.p1(6);
+                packet.p3(value);
+            }
+            Js5Request::Disconnect => {
+                packet.p1(7);
+                packet.p3(0);
+            }
+        }
+    }
+}
+
+/// A group sent in response to a [Js5Request::Group].
+///
+/// On the wire the response is made up of the archive, group and container, split into blocks
+/// of [BLOCK_SIZE] bytes. Every block after the first begins with [BLOCK_MARKER], and every byte
+/// is XORed with the connection's key when one has been set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Js5Response {
+    pub prefetch: bool,
+    pub archive: u8,
+    pub group: u16,
+    /// The raw container, beginning with its compression type and length.
+    pub container: Vec<u8>,
+}
+
+impl Js5Response {
+    /// Writes this response into the packet, XORing every byte written with `key`.
+    pub fn encode(&self, packet: &mut Packet, key: u8) -> Result<(), PacketError> {
+        if self.container.len() < 5 {
+            return error(format!("Container for group {} is too short.", self.group));
+        }
+
+        let mut raw = Vec::with_capacity(3 + self.container.len());
+        raw.push(self.archive);
+        raw.extend_from_slice(&self.group.to_be_bytes());
+        raw.push(self.container[0] | if self.prefetch { PREFETCH_FLAG } else { 0 });
+        raw.extend_from_slice(&self.container[1..]);
+
+        let mut framed = Vec::with_capacity(framed_len(raw.len()));
+        for byte in raw {
+            if !framed.is_empty() && framed.len() % BLOCK_SIZE == 0 {
+                framed.push(BLOCK_MARKER);
+            }
+            framed.push(byte);
+        }
+
+        if key != 0 {
+            framed.iter_mut().for_each(|byte| *byte ^= key);
+        }
+        packet.write_all(&framed)?;
+        Ok(())
+    }
+
+    /// Decodes a single response, returning `None` without consuming any bytes if the complete
+    /// response is not yet available. Every byte is XORed with `key` before being read.
+    pub fn decode(packet: &mut Packet, key: u8) -> Result<Option<Js5Response>, PacketError> {
+        let available = packet.slice_remaining();
+        if available.len() < 8 {
+            return Ok(None);
+        }
+
+        let mut header = Packet::from(&available[..8]);
+        if key != 0 {
+            header.get_inner_mut().iter_mut().for_each(|byte| *byte ^= key);
+        }
+        let archive = header.g1()?;
+        let group = header.g2()?;
+        let compression = header.g1()?;
+        let len = header.g4()? as usize;
+
+        let container_len = 5 + len + if compression & !PREFETCH_FLAG == 0 { 0 } else { 4 };
+        let framed_len = framed_len(3 + container_len);
+        if available.len() < framed_len {
+            return Ok(None);
+        }
+
+        let mut raw = Vec::with_capacity(3 + container_len);
+        for (i, byte) in available[..framed_len].iter().enumerate() {
+            if i > 0 && i % BLOCK_SIZE == 0 {
+                if byte ^ key != BLOCK_MARKER {
+                    return error(format!("Missing block marker at offset {} of group {}.", i, group));
+                }
+                continue;
+            }
+            raw.push(byte ^ key);
+        }
+        packet.skip(framed_len);
+
+        let mut container = raw.split_off(3);
+        container[0] &= !PREFETCH_FLAG;
+        Ok(Some(Js5Response {
+            prefetch: compression & PREFETCH_FLAG != 0,
+            archive,
+            group,
+            container,
+        }))
+    }
+}
+
+/// Returns the length of `raw_len` bytes once split into blocks.
+fn framed_len(raw_len: usize) -> usize {
+    if raw_len <= BLOCK_SIZE {
+        raw_len
+    } else {
+        raw_len + (raw_len - BLOCK_SIZE).div_ceil(BLOCK_SIZE - 1)
+    }
+}
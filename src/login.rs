@@ -0,0 +1,715 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use crate::rsa::{RsaKeyPair, RsaPublicKey};
+use std::io::Write;
+
+/// The service id sent by the client to begin a game login.
+pub const SERVICE_LOGIN: u8 = 14;
+
+/// The service id sent by the client to begin an update (js5) session.
+pub const SERVICE_UPDATE: u8 = 15;
+
+/// The login type sent by a client connecting for the first time.
+pub const LOGIN_NEW: u8 = 16;
+
+/// The login type sent by a client reconnecting after losing its connection.
+pub const LOGIN_RECONNECT: u8 = 18;
+
+/// The byte every RSA block begins with once decrypted.
+pub const RSA_MAGIC: u8 = 10;
+
+/// The byte preceding the revision in the [Legacy](LoginLayout::Legacy) layout.
+const LEGACY_MAGIC: u8 = 255;
+
+/// The layout of the login block, which differs between revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginLayout {
+    /// The 317-era layout:
+    ///
+    /// - handshake: `g1` service, `g1` username hash
+    /// - header: `g1` login type, `g1` payload size
+    /// - payload: `g1` 255, `g2` revision, `g1` low memory, `g4` per crc, `g1` rsa block size, rsa block
+    /// - rsa block: `g1` 10, `g4` seed x4, `g4` uid, `gjstr` username, `gjstr` password
+    ///
+    /// The last two seeds hold the server key sent during the handshake, and any bytes
+    /// remaining in the rsa block are returned as [extra](LoginRequest::extra).
+    Legacy,
+    /// The layout used by later revisions, where most of the payload is XTEA encrypted using the
+    /// ISAAC seeds as the key:
+    ///
+    /// - handshake: `g1` service, `g1` username hash
+    /// - header: `g1` login type, `g2` payload size
+    /// - payload: `g4` revision, `g1` low memory, `g2` rsa block size, rsa block, encrypted remainder
+    /// - rsa block: `g1` 10, `g4` seed x4, `gjstr` password
+    /// - remainder: `gjstr` username, `g4` per crc, revision-specific data
+    ///
+    /// As with the legacy layout, the last two seeds hold the server key.
+    Xtea,
+}
+
+/// Describes what the decoder expects from connecting clients.
+#[derive(Debug, Clone)]
+pub struct LoginConfig {
+    pub layout: LoginLayout,
+    pub revision: u32,
+    /// The amount of archive CRCs the client sends.
+    pub crc_count: usize,
+    /// The key pair the RSA block is decrypted with, or `None` if the block is sent unencrypted.
+    pub rsa: Option<RsaKeyPair>,
+}
+
+/// A decoded login request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginRequest {
+    pub reconnecting: bool,
+    pub revision: u32,
+    pub low_memory: bool,
+    pub crcs: Vec<u32>,
+    /// The ISAAC seeds, the first two chosen by the client and the last two holding the server key.
+    pub seeds: [i32; 4],
+    pub server_key: u64,
+    /// The client uid, only sent in the [Legacy](LoginLayout::Legacy) layout.
+    pub uid: u32,
+    pub username: String,
+    pub password: String,
+    /// Any revision-specific data following the fields above, decrypted if necessary.
+    pub extra: Vec<u8>,
+}
+
+/// A response code sent to the client in reply to a login request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginResponse {
+    /// The client should retry the login after a short delay.
+    Retry,
+    Success { rights: u8, flagged: bool },
+    InvalidCredentials,
+    Disabled,
+    AlreadyLoggedIn,
+    /// The client is out of date and must be reloaded.
+    GameUpdated,
+    WorldFull,
+    LoginServerOffline,
+    TooManyConnections,
+    BadSessionId,
+    Rejected,
+    MembersWorld,
+    CouldNotComplete,
+    ServerUpdating,
+    ReconnectSuccess,
+    TooManyAttempts,
+    MembersArea,
+    Locked,
+    InvalidLoginServer,
+    /// The player is being moved to another world, and can log in after the given seconds.
+    Transferring { seconds: u8 },
+}
+
+impl LoginResponse {
+    /// Returns the response code sent for this response.
+    pub fn code(&self) -> u8 {
+        match self {
+            LoginResponse::Retry => 1,
+            LoginResponse::Success { .. } => 2,
+            LoginResponse::InvalidCredentials => 3,
+            LoginResponse::Disabled => 4,
+            LoginResponse::AlreadyLoggedIn => 5,
+            LoginResponse::GameUpdated => 6,
+            LoginResponse::WorldFull => 7,
+            LoginResponse::LoginServerOffline => 8,
+            LoginResponse::TooManyConnections => 9,
+            LoginResponse::BadSessionId => 10,
+            LoginResponse::Rejected => 11,
+            LoginResponse::MembersWorld => 12,
+            LoginResponse::CouldNotComplete => 13,
+            LoginResponse::ServerUpdating => 14,
+            LoginResponse::ReconnectSuccess => 15,
+            LoginResponse::TooManyAttempts => 16,
+            LoginResponse::MembersArea => 17,
+            LoginResponse::Locked => 18,
+            LoginResponse::InvalidLoginServer => 20,
+            LoginResponse::Transferring { .. } => 21,
+        }
+    }
+
+    /// Writes this response into the packet.
+    pub fn encode(&self, packet: &mut Packet) {
+        packet.p1(self.code());
+        match *self {
+            LoginResponse::Success { rights, flagged } => {
+                packet.p1(rights);
+                packet.p1(flagged as u8);
+            }
+            LoginResponse::Transferring { seconds } => packet.p1(seconds),
+            _ => {}
+        }
+    }
+
+    /// Decodes a response from the packet, as read by the client.
+    pub fn decode(packet: &mut Packet) -> Result<LoginResponse, PacketError> {
+        let code = packet.g1()?;
+        Ok(match code {
+            1 => LoginResponse::Retry,
+            2 => LoginResponse::Success { rights: packet.g1()?, flagged: packet.g1()? == 1 },
+            3 => LoginResponse::InvalidCredentials,
+            4 => LoginResponse::Disabled,
+            5 => LoginResponse::AlreadyLoggedIn,
+            6 => LoginResponse::GameUpdated,
+            7 => LoginResponse::WorldFull,
+            8 => LoginResponse::LoginServerOffline,
+            9 => LoginResponse::TooManyConnections,
+            10 => LoginResponse::BadSessionId,
+            11 => LoginResponse::Rejected,
+            12 => LoginResponse::MembersWorld,
+            13 => LoginResponse::CouldNotComplete,
+            14 => LoginResponse::ServerUpdating,
+            15 => LoginResponse::ReconnectSuccess,
+            16 => LoginResponse::TooManyAttempts,
+            17 => LoginResponse::MembersArea,
+            18 => LoginResponse::Locked,
+            20 => LoginResponse::InvalidLoginServer,
+            21 => LoginResponse::Transferring { seconds: packet.g1()? },
+            _ => return error(format!("Unknown login response code {}.", code)),
+        })
+    }
+}
+
+/// Something the [LoginDecoder] needs the caller to act upon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginEvent {
+    /// The client wishes to log in. The caller should reply using
+    /// [handshake_response](LoginDecoder::handshake_response).
+    LoginHandshake { name_hash: u8 },
+    /// The client wishes to begin an update session with the given revision. No further bytes
+    /// are consumed by the decoder.
+    UpdateHandshake { revision: u32 },
+    /// The login request was rejected before being fully decoded, such as for an out of date
+    /// revision. The caller should send the response and close the connection.
+    Rejected(LoginResponse),
+    /// The login request was fully decoded.
+    Request(Box<LoginRequest>),
+}
+
+/// The stage of the login sequence the decoder is waiting on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginState {
+    Handshake,
+    Login,
+    Complete,
+}
+
+/// A state machine decoding the login sequence as bytes arrive from the client.
+///
+/// Each call to [decode](LoginDecoder::decode) consumes at most one stage of the sequence. If a
+/// stage is not yet complete, no bytes are consumed and `None` is returned so the caller can
+/// retry once more bytes have arrived.
+#[derive(Debug, Clone)]
+pub struct LoginDecoder {
+    config: LoginConfig,
+    server_key: u64,
+    state: LoginState,
+}
+
+impl LoginDecoder {
+    /// Creates a decoder using the `server_key` that is sent to the client during the handshake
+    /// and expected back within the RSA block.
+    pub fn new(config: LoginConfig, server_key: u64) -> Self {
+        Self {
+            config,
+            server_key,
+            state: LoginState::Handshake,
+        }
+    }
+
+    pub fn state(&self) -> LoginState {
+        self.state
+    }
+
+    pub fn server_key(&self) -> u64 {
+        self.server_key
+    }
+
+    /// Writes the reply to a [LoginHandshake](LoginEvent::LoginHandshake), holding the server key.
+    pub fn handshake_response(&self, packet: &mut Packet) {
+        if self.config.layout == LoginLayout::Legacy {
+            packet.p8(0);
+        }
+        packet.p1(0);
+        packet.p8(self.server_key);
+    }
+
+    /// Decodes the next stage of the login sequence from the packet.
+    pub fn decode(&mut self, packet: &mut Packet) -> Result<Option<LoginEvent>, PacketError> {
+        let start = packet.get_pos();
+        let event = match self.state {
+            LoginState::Handshake => self.decode_handshake(packet),
+            LoginState::Login => self.decode_login(packet),
+            LoginState::Complete => return error("The login sequence has already completed.".to_string()),
+        };
+
+        match event {
+            Ok(None) => {
+                packet.set_pos(start)?;
+                Ok(None)
+            }
+            Ok(Some(event)) => {
+                self.state = match event {
+                    LoginEvent::LoginHandshake { .. } => LoginState::Login,
+                    _ => LoginState::Complete,
+                };
+                Ok(Some(event))
+            }
+            Err(e) => {
+                self.state = LoginState::Complete;
+                Err(e)
+            }
+        }
+    }
+
+    fn decode_handshake(&mut self, packet: &mut Packet) -> Result<Option<LoginEvent>, PacketError> {
+        let service = match packet.peek() {
+            Some(service) => service,
+            None => return Ok(None),
+        };
+
+        match service {
+            SERVICE_LOGIN if packet.has_available(2) => {
+                packet.skip(1);
+                Ok(Some(LoginEvent::LoginHandshake { name_hash: packet.g1()? }))
+            }
+            SERVICE_UPDATE if packet.has_available(5) => {
+                packet.skip(1);
+                Ok(Some(LoginEvent::UpdateHandshake { revision: packet.g4()? }))
+            }
+            SERVICE_LOGIN | SERVICE_UPDATE => Ok(None),
+            _ => error(format!("Unknown login service {}.", service)),
+        }
+    }
+
+    fn decode_login(&mut self, packet: &mut Packet) -> Result<Option<LoginEvent>, PacketError> {
+        let header_len = match self.config.layout {
+            LoginLayout::Legacy => 2,
+            LoginLayout::Xtea => 3,
+        };
+        if !packet.has_available(header_len) {
+            return Ok(None);
+        }
+
+        let login_type = packet.g1()?;
+        if login_type != LOGIN_NEW && login_type != LOGIN_RECONNECT {
+            return error(format!("Unknown login type {}.", login_type));
+        }
+        let size = match self.config.layout {
+            LoginLayout::Legacy => packet.g1()? as usize,
+            LoginLayout::Xtea => packet.g2()? as usize,
+        };
+        if !packet.has_available(size) {
+            return Ok(None);
+        }
+
+        let mut payload = Packet::from(packet.gdata(size));
+        let reconnecting = login_type == LOGIN_RECONNECT;
+        match self.config.layout {
+            LoginLayout::Legacy => self.decode_legacy(&mut payload, reconnecting),
+            LoginLayout::Xtea => self.decode_xtea(&mut payload, reconnecting),
+        }
+        .map(Some)
+    }
+
+    fn decode_legacy(&self, payload: &mut Packet, reconnecting: bool) -> Result<LoginEvent, PacketError> {
+        if payload.g1()? != LEGACY_MAGIC {
+            return error("Invalid login payload magic.".to_string());
+        }
+        let revision = payload.g2()? as u32;
+        if revision != self.config.revision {
+            return Ok(LoginEvent::Rejected(LoginResponse::GameUpdated));
+        }
+        let low_memory = payload.g1()? == 1;
+        let crcs = self.decode_crcs(payload)?;
+
+        let block_len = payload.g1()? as usize;
+        let mut block = self.decrypt_block(payload, block_len)?;
+        let (seeds, server_key) = self.decode_seeds(&mut block)?;
+        let uid = block.g4()?;
+        let username = block.gjstr()?;
+        let password = block.gjstr()?;
+        Ok(LoginEvent::Request(Box::new(LoginRequest {
+            reconnecting,
+            revision,
+            low_memory,
+            crcs,
+            seeds,
+            server_key,
+            uid,
+            username,
+            password,
+            extra: block.to_vec(),
+        })))
+    }
+
+    fn decode_xtea(&self, payload: &mut Packet, reconnecting: bool) -> Result<LoginEvent, PacketError> {
+        let revision = payload.g4()?;
+        if revision != self.config.revision {
+            return Ok(LoginEvent::Rejected(LoginResponse::GameUpdated));
+        }
+        let low_memory = payload.g1()? == 1;
+
+        let block_len = payload.g2()? as usize;
+        let mut block = self.decrypt_block(payload, block_len)?;
+        let (seeds, server_key) = self.decode_seeds(&mut block)?;
+        let password = block.gjstr()?;
+
+        let mut remainder = Packet::from(payload.to_vec());
+        let end = remainder.len();
+        remainder.tiny_key_decrypt_range(&seeds, 0, end)?;
+        let username = remainder.gjstr()?;
+        let crcs = self.decode_crcs(&mut remainder)?;
+
+        Ok(LoginEvent::Request(Box::new(LoginRequest {
+            reconnecting,
+            revision,
+            low_memory,
+            crcs,
+            seeds,
+            server_key,
+            uid: 0,
+            username,
+            password,
+            extra: remainder.to_vec(),
+        })))
+    }
+
+    fn decode_crcs(&self, packet: &mut Packet) -> Result<Vec<u32>, PacketError> {
+        (0..self.config.crc_count).map(|_| packet.g4()).collect()
+    }
+
+    /// Reads and decrypts the RSA block, verifying its magic byte.
+    fn decrypt_block(&self, payload: &mut Packet, len: usize) -> Result<Packet, PacketError> {
+        if !payload.has_available(len) {
+            return error(format!(
+                "Not enough data for rsa block. Needed {}, have {}.",
+                len,
+                payload.available_count()
+            ));
+        }
+
+        let data = payload.gdata(len);
+        let mut block = match &self.config.rsa {
+            Some(key) => Packet::from(key.decrypt(&data)),
+            None => Packet::from(data),
+        };
+
+        if block.g1()? != RSA_MAGIC {
+            return error("Invalid rsa block magic. The block may have been encrypted with the wrong key.".to_string());
+        }
+        Ok(block)
+    }
+
+    /// Reads the ISAAC seeds, verifying that the last two hold the server key.
+    fn decode_seeds(&self, block: &mut Packet) -> Result<([i32; 4], u64), PacketError> {
+        let seeds = [block.g4s()?, block.g4s()?, block.g4s()?, block.g4s()?];
+        let server_key = (seeds[2] as u32 as u64) << 32 | seeds[3] as u32 as u64;
+        if server_key != self.server_key {
+            return error("Server key mismatch in login block.".to_string());
+        }
+        Ok((seeds, server_key))
+    }
+}
+
+/// The display mode sent by clients of the [Xtea](LoginLayout::Xtea) layout.
+///
+/// - `g1` mode, `g2` width, `g2` height
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayMode {
+    /// 0 for fixed, 1 for resizable and 2 for fullscreen.
+    pub mode: u8,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl DisplayMode {
+    pub fn encode(&self, packet: &mut Packet) {
+        packet.p1(self.mode);
+        packet.p2(self.width);
+        packet.p2(self.height);
+    }
+
+    pub fn decode(packet: &mut Packet) -> Result<DisplayMode, PacketError> {
+        Ok(DisplayMode {
+            mode: packet.g1()?,
+            width: packet.g2()?,
+            height: packet.g2()?,
+        })
+    }
+}
+
+/// A summary of the machine the client is running on.
+///
+/// - `g1` os, `g1` 64-bit, `g1` os version, `g1` java vendor, `g1` java version x3, `g2` max
+///   memory in megabytes, `g1` processor count
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MachineInfo {
+    pub os: u8,
+    pub x64: bool,
+    pub os_version: u8,
+    pub java_vendor: u8,
+    pub java_version: [u8; 3],
+    pub max_memory: u16,
+    pub processors: u8,
+}
+
+impl MachineInfo {
+    pub fn encode(&self, packet: &mut Packet) {
+        packet.p1(self.os);
+        packet.p1(self.x64 as u8);
+        packet.p1(self.os_version);
+        packet.p1(self.java_vendor);
+        self.java_version.iter().for_each(|version| packet.p1(*version));
+        packet.p2(self.max_memory);
+        packet.p1(self.processors);
+    }
+
+    pub fn decode(packet: &mut Packet) -> Result<MachineInfo, PacketError> {
+        Ok(MachineInfo {
+            os: packet.g1()?,
+            x64: packet.g1()? == 1,
+            os_version: packet.g1()?,
+            java_vendor: packet.g1()?,
+            java_version: [packet.g1()?, packet.g1()?, packet.g1()?],
+            max_memory: packet.g2()?,
+            processors: packet.g1()?,
+        })
+    }
+}
+
+/// Builds the login sequence a client would send, for exercising servers from bots and tests.
+///
+/// The output matches what [LoginDecoder] expects for the same [LoginLayout]. The
+/// [display mode](LoginRequestBuilder::display_mode), [machine info](LoginRequestBuilder::machine_info)
+/// and [extra](LoginRequestBuilder::extra) bytes are written in that order where the layout places
+/// revision-specific data, and so are returned to the server as [LoginRequest::extra].
+#[derive(Debug, Clone)]
+pub struct LoginRequestBuilder {
+    layout: LoginLayout,
+    revision: u32,
+    reconnecting: bool,
+    low_memory: bool,
+    crcs: Vec<u32>,
+    seeds: [i32; 2],
+    uid: u32,
+    username: String,
+    password: String,
+    display_mode: Option<DisplayMode>,
+    machine_info: Option<MachineInfo>,
+    extra: Vec<u8>,
+    rsa: Option<RsaPublicKey>,
+}
+
+impl LoginRequestBuilder {
+    pub fn new(layout: LoginLayout, revision: u32) -> Self {
+        Self {
+            layout,
+            revision,
+            reconnecting: false,
+            low_memory: false,
+            crcs: Vec::new(),
+            seeds: [0; 2],
+            uid: 0,
+            username: String::new(),
+            password: String::new(),
+            display_mode: None,
+            machine_info: None,
+            extra: Vec::new(),
+            rsa: None,
+        }
+    }
+
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = username.into();
+        self.password = password.into();
+        self
+    }
+
+    pub fn reconnecting(mut self, reconnecting: bool) -> Self {
+        self.reconnecting = reconnecting;
+        self
+    }
+
+    pub fn low_memory(mut self, low_memory: bool) -> Self {
+        self.low_memory = low_memory;
+        self
+    }
+
+    pub fn crcs(mut self, crcs: Vec<u32>) -> Self {
+        self.crcs = crcs;
+        self
+    }
+
+    /// Sets the two ISAAC seeds chosen by the client. The remaining seeds hold the server key.
+    pub fn seeds(mut self, seeds: [i32; 2]) -> Self {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Sets the client uid, only sent in the [Legacy](LoginLayout::Legacy) layout.
+    pub fn uid(mut self, uid: u32) -> Self {
+        self.uid = uid;
+        self
+    }
+
+    pub fn display_mode(mut self, display_mode: DisplayMode) -> Self {
+        self.display_mode = Some(display_mode);
+        self
+    }
+
+    pub fn machine_info(mut self, machine_info: MachineInfo) -> Self {
+        self.machine_info = Some(machine_info);
+        self
+    }
+
+    /// Appends raw revision-specific bytes, written after the display mode and machine info.
+    pub fn extra(mut self, extra: &[u8]) -> Self {
+        self.extra.extend_from_slice(extra);
+        self
+    }
+
+    /// Sets the public key the RSA block is encrypted with. Without one the block is sent
+    /// unencrypted.
+    pub fn rsa(mut self, key: RsaPublicKey) -> Self {
+        self.rsa = Some(key);
+        self
+    }
+
+    /// Returns the ISAAC seeds the request will carry once the server key is known.
+    pub fn isaac_seeds(&self, server_key: u64) -> [i32; 4] {
+        [self.seeds[0], self.seeds[1], (server_key >> 32) as i32, server_key as i32]
+    }
+
+    /// Writes the initial handshake, holding the service and a hash of the username.
+    pub fn handshake(&self, packet: &mut Packet) {
+        packet.p1(SERVICE_LOGIN);
+        packet.p1((username_to_long(&self.username) >> 16 & 31) as u8);
+    }
+
+    /// Reads the server's reply to the [handshake](LoginRequestBuilder::handshake), returning the
+    /// server key or `None` without consuming any bytes if the reply is incomplete.
+    pub fn decode_handshake_response(&self, packet: &mut Packet) -> Result<Option<u64>, PacketError> {
+        let skip = if self.layout == LoginLayout::Legacy { 8 } else { 0 };
+        if !packet.has_available(skip + 9) {
+            return Ok(None);
+        }
+
+        packet.skip(skip);
+        let status = packet.g1()?;
+        if status != 0 {
+            return error(format!("Unexpected handshake response {}.", status));
+        }
+        Ok(Some(packet.g8()?))
+    }
+
+    /// Writes the login request, including the header, using the `server_key` received during
+    /// the handshake.
+    pub fn encode(&self, packet: &mut Packet, server_key: u64) -> Result<(), PacketError> {
+        let seeds = self.isaac_seeds(server_key);
+        let mut block = Packet::empty();
+        block.p1(RSA_MAGIC);
+        seeds.iter().for_each(|seed| block.p4(*seed as u32));
+
+        let mut payload = Packet::empty();
+        match self.layout {
+            LoginLayout::Legacy => {
+                block.p4(self.uid);
+                block.pjstr(&self.username);
+                block.pjstr(&self.password);
+                self.encode_extra(&mut block)?;
+
+                payload.p1(LEGACY_MAGIC);
+                payload.p2(self.revision as u16);
+                payload.p1(self.low_memory as u8);
+                self.crcs.iter().for_each(|crc| payload.p4(*crc));
+                self.encode_block(&mut payload, block)?;
+            }
+            LoginLayout::Xtea => {
+                block.pjstr(&self.password);
+
+                let mut remainder = Packet::empty();
+                remainder.pjstr(&self.username);
+                self.crcs.iter().for_each(|crc| remainder.p4(*crc));
+                self.encode_extra(&mut remainder)?;
+                let end = remainder.len();
+                remainder.tiny_key_encrypt_range(&seeds, 0, end)?;
+
+                payload.p4(self.revision);
+                payload.p1(self.low_memory as u8);
+                self.encode_block(&mut payload, block)?;
+                remainder.set_pos(0)?;
+                payload.write_all(remainder.as_ref())?;
+            }
+        }
+
+        packet.p1(if self.reconnecting { LOGIN_RECONNECT } else { LOGIN_NEW });
+        match self.layout {
+            LoginLayout::Legacy if payload.len() > u8::MAX as usize => {
+                return error(format!("Login payload of {} bytes is too large.", payload.len()));
+            }
+            LoginLayout::Legacy => packet.p1(payload.len() as u8),
+            LoginLayout::Xtea => packet.p2(payload.len() as u16),
+        }
+        payload.set_pos(0)?;
+        packet.write_all(payload.as_ref())?;
+        Ok(())
+    }
+
+    /// Writes the RSA block into the payload prefixed with its length, encrypting it first if a
+    /// key is set.
+    fn encode_block(&self, payload: &mut Packet, mut block: Packet) -> Result<(), PacketError> {
+        match (&self.rsa, self.layout) {
+            (Some(key), LoginLayout::Legacy) => block.rsa_encrypt_g1(&key.exponent, &key.modulus),
+            (Some(key), LoginLayout::Xtea) => block.rsa_encrypt(&key.exponent, &key.modulus),
+            (None, _) => {
+                block.set_pos(0)?;
+                let data = block.to_vec();
+                if self.layout == LoginLayout::Legacy && data.len() > u8::MAX as usize {
+                    return error(format!("RSA block of {} bytes is too large.", data.len()));
+                }
+                block = Packet::empty();
+                match self.layout {
+                    LoginLayout::Legacy => block.p1(data.len() as u8),
+                    LoginLayout::Xtea => block.p2(data.len() as u16),
+                }
+                block.write_all(&data)?;
+            }
+        }
+        block.set_pos(0)?;
+        payload.write_all(block.as_ref())?;
+        Ok(())
+    }
+
+    fn encode_extra(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        if let Some(display_mode) = &self.display_mode {
+            display_mode.encode(packet);
+        }
+        if let Some(machine_info) = &self.machine_info {
+            machine_info.encode(packet);
+        }
+        packet.write_all(&self.extra)?;
+        Ok(())
+    }
+}
+
+/// Encodes a username as a base-37 integer, as done by the client when hashing it.
+fn username_to_long(username: &str) -> u64 {
+    let mut value = 0u64;
+    for c in username.chars().take(12) {
+        value = value.wrapping_mul(37);
+        value += match c {
+            'A'..='Z' => c as u64 - 'A' as u64 + 1,
+            'a'..='z' => c as u64 - 'a' as u64 + 1,
+            '0'..='9' => c as u64 - '0' as u64 + 27,
+            _ => 0,
+        };
+    }
+    while value % 37 == 0 && value != 0 {
+        value /= 37;
+    }
+    value
+}
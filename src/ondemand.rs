@@ -0,0 +1,136 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::io::Write;
+
+/// The maximum amount of file data carried by a single response chunk.
+pub const CHUNK_SIZE: usize = 500;
+
+/// The length of every request sent by the client.
+pub const REQUEST_LEN: usize = 4;
+
+/// The length of the header preceding the data of every response chunk.
+pub const CHUNK_HEADER_LEN: usize = 6;
+
+/// A file request sent by a pre-JS5 client: the cache index, the file within it, and the
+/// priority the client assigns to the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OnDemandRequest {
+    pub index: u8,
+    pub file: u16,
+    pub priority: u8,
+}
+
+impl OnDemandRequest {
+    /// Decodes a single request, returning `None` without consuming any bytes if a complete
+    /// request is not yet available.
+    pub fn decode(packet: &mut Packet) -> Result<Option<OnDemandRequest>, PacketError> {
+        if !packet.has_available(REQUEST_LEN) {
+            return Ok(None);
+        }
+        Ok(Some(OnDemandRequest {
+            index: packet.g1()?,
+            file: packet.g2()?,
+            priority: packet.g1()?,
+        }))
+    }
+
+    /// Writes this request into the packet.
+    pub fn encode(&self, packet: &mut Packet) {
+        packet.p1(self.index);
+        packet.p2(self.file);
+        packet.p1(self.priority);
+    }
+}
+
+/// A single chunk of an [OnDemandResponse]. Every chunk repeats the index, file and total size
+/// of the file, followed by the position of the chunk and up to [CHUNK_SIZE] bytes of data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnDemandChunk {
+    pub index: u8,
+    pub file: u16,
+    pub size: u16,
+    pub chunk: u8,
+    pub data: Vec<u8>,
+}
+
+impl OnDemandChunk {
+    /// Decodes a single chunk, returning `None` without consuming any bytes if the complete
+    /// chunk is not yet available.
+    pub fn decode(packet: &mut Packet) -> Result<Option<OnDemandChunk>, PacketError> {
+        if !packet.has_available(CHUNK_HEADER_LEN) {
+            return Ok(None);
+        }
+
+        let start = packet.get_pos();
+        let index = packet.g1()?;
+        let file = packet.g2()?;
+        let size = packet.g2()?;
+        let chunk = packet.g1()?;
+
+        let offset = chunk as usize * CHUNK_SIZE;
+        if size != 0 && offset >= size as usize {
+            return error(format!("Chunk {} lies beyond the end of file {} ({} bytes).", chunk, file, size));
+        }
+        let len = (size as usize).saturating_sub(offset).min(CHUNK_SIZE);
+        if !packet.has_available(len) {
+            packet.set_pos(start)?;
+            return Ok(None);
+        }
+
+        Ok(Some(OnDemandChunk { index, file, size, chunk, data: packet.gdata(len) }))
+    }
+
+    /// Writes this chunk into the packet.
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        packet.p1(self.index);
+        packet.p2(self.file);
+        packet.p2(self.size);
+        packet.p1(self.chunk);
+        packet.write_all(&self.data)?;
+        Ok(())
+    }
+}
+
+/// A complete file sent in response to an [OnDemandRequest]. A file with no data is sent as a
+/// single empty chunk, signalling to the client that the file does not exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnDemandResponse {
+    pub index: u8,
+    pub file: u16,
+    pub data: Vec<u8>,
+}
+
+impl OnDemandResponse {
+    /// Splits this response into the chunks sent to the client.
+    pub fn chunks(&self) -> Result<Vec<OnDemandChunk>, PacketError> {
+        if self.data.len() > u16::MAX as usize {
+            return error(format!("File {} is too large to send ({} bytes).", self.file, self.data.len()));
+        }
+
+        let size = self.data.len() as u16;
+        if self.data.is_empty() {
+            return Ok(vec![OnDemandChunk { index: self.index, file: self.file, size, chunk: 0, data: Vec::new() }]);
+        }
+
+        Ok(self
+            .data
+            .chunks(CHUNK_SIZE)
+            .enumerate()
+            .map(|(chunk, data)| OnDemandChunk {
+                index: self.index,
+                file: self.file,
+                size,
+                chunk: chunk as u8,
+                data: data.to_vec(),
+            })
+            .collect())
+    }
+
+    /// Writes every chunk of this response into the packet.
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        for chunk in self.chunks()? {
+            chunk.encode(packet)?;
+        }
+        Ok(())
+    }
+}
@@ -152,10 +152,7 @@ impl<'a> BitWriter<'a> {
             return Ok(());
         }
 
-        let required_len = self.packet.pos + (self.bit_pos + bit_count + 7) / 8;
-        if required_len > self.packet.bytes.len() {
-            self.packet.bytes.resize(required_len, 0);
-        }
+        self.packet.ensure_capacity((self.bit_pos + bit_count).div_ceil(8));
 
         let max_value = if bit_count == 32 { u32::MAX } else { (1 << bit_count) - 1 };
         let masked_value = value & max_value;
@@ -172,17 +169,18 @@ impl<'a> BitWriter<'a> {
             let clear_mask_shift = bits_available_in_byte - bits_to_write;
             let clear_mask = !((BIT_MASKS[bits_to_write] as u8) << clear_mask_shift);
 
-            self.packet.bytes[self.packet.pos] &= clear_mask;
+            let index = self.packet.writer_index();
+            self.packet.bytes[index] &= clear_mask;
 
             let set_mask = (bits_from_value as u8) << clear_mask_shift;
-            self.packet.bytes[self.packet.pos] |= set_mask;
+            self.packet.bytes[index] |= set_mask;
 
             self.bit_pos += bits_to_write;
             bits_remaining -= bits_to_write;
 
             if self.bit_pos == 8 {
                 self.bit_pos = 0;
-                self.packet.pos += 1;
+                self.packet.advance_writer(1);
             }
         }
 
@@ -199,11 +197,7 @@ impl<'a> BitWriter<'a> {
 impl<'a> Drop for BitWriter<'a> {
     fn drop(&mut self) {
         if self.bit_pos > 0 {
-            self.packet.pos += 1;
-        }
-
-        if self.packet.pos > self.packet.len {
-            self.packet.len = self.packet.pos;
+            self.packet.advance_writer(1);
         }
     }
 }
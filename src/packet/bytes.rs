@@ -2,7 +2,7 @@ use crate::packet::error::{error, PacketError};
 use num_bigint::BigInt;
 use std::cmp::min;
 use std::fmt::Debug;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::ops::{Range, RangeInclusive};
 use std::io;
 
@@ -28,33 +28,60 @@ macro_rules! g {
 
 macro_rules! p {
     ($this:tt,  $value:tt) => {{
-        let pos = $this.pos;
+        let pos = $this.writer_index();
         let slice_len = $value.len();
         $this.ensure_capacity(slice_len);
 
         $this.bytes[pos..pos + slice_len].copy_from_slice($value);
-        $this.pos += slice_len;
-        if $this.pos > $this.len {
-            $this.len = $this.pos;
-        }
+        $this.advance_writer(slice_len);
     }};
 }
 
+/// How a [Packet] positions reads and writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexMode {
+    /// Reads and writes share a single cursor, `pos`, with `len` marking the furthest byte
+    /// written. Reading back written data therefore requires moving the cursor, such as with
+    /// [set_pos](Packet::set_pos) or [flip](Packet::flip).
+    #[default]
+    Shared,
+    /// Reads advance the reader index while writes append at the writer index, so a packet can
+    /// be consumed while it is still being written to. The readable bytes are those between the
+    /// two indices.
+    Split,
+}
+
+/// A byte buffer holding the big-endian and obfuscated types used throughout the protocol.
+///
+/// Every packet upholds `pos <= len <= capacity`:
+///
+/// - [len](Packet::len) is the amount of bytes written, and therefore readable, from the start
+///   of the buffer. Reads never go past it and writes extend it to cover the bytes written.
+/// - [capacity](Packet::capacity) is the amount of zero-initialized storage available before
+///   the buffer has to grow, such as reserved by [new](Packet::new).
+/// - `pos` is the reader index, from which the remaining `len - pos` bytes are readable.
 #[derive(Clone)]
 pub struct Packet {
     pub(crate) bytes: Vec<u8>,
+    /// The reader index, which is also the writer index in [IndexMode::Shared].
     pub(crate) pos: usize,
+    /// The end of the readable bytes, which is also the writer index in [IndexMode::Split].
     pub(crate) len: usize,
+    pub(crate) mode: IndexMode,
+    /// The reader and writer index saved by [mark](Packet::mark).
+    pub(crate) marked: (usize, usize),
 }
 
 impl Packet {
-    /// Creates a new byte buffer whose contents are initialized with 0.
+    /// Creates a new, empty byte buffer with `capacity` bytes of storage initialized with 0.
     pub fn new(capacity: usize) -> Self {
         let buf = vec![0u8; capacity];
         Self {
             bytes: buf,
             pos: 0,
             len: 0,
+            mode: IndexMode::Shared,
+            marked: (0, 0),
         }
     }
 
@@ -63,9 +90,24 @@ impl Packet {
             bytes: Vec::with_capacity(0),
             pos: 0,
             len: 0,
+            mode: IndexMode::Shared,
+            marked: (0, 0),
         }
     }
 
+    /// Returns the packet using `mode` to position reads and writes, keeping its current
+    /// indices.
+    pub fn with_index_mode(mut self, mode: IndexMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn index_mode(&self) -> IndexMode {
+        self.mode
+    }
+
+    /// Returns the underlying storage. Changing its length leaves the packet holding an invalid
+    /// length until [set_len](Packet::set_len) is called.
     pub fn get_inner_mut(&mut self) -> &mut Vec<u8> {
         &mut self.bytes
     }
@@ -79,7 +121,7 @@ impl Packet {
         &self.bytes[self.pos..self.len]
     }
 
-    /// Returns a mutable slice of the entire written buffer.
+    /// Returns a mutable slice of the remaining readable bytes.
     pub fn as_mut_slice_all(&mut self) -> &mut [u8] {
         &mut self.bytes[self.pos..self.len]
     }
@@ -97,6 +139,8 @@ impl From<Vec<u8>> for Packet {
             pos: 0,
             len: value.len(),
             bytes: value,
+            mode: IndexMode::Shared,
+            marked: (0, 0),
         }
     }
 }
@@ -107,6 +151,8 @@ impl From<&[u8]> for Packet {
             pos: 0,
             len: value.len(),
             bytes: value.to_vec(),
+            mode: IndexMode::Shared,
+            marked: (0, 0),
         }
     }
 }
@@ -126,7 +172,8 @@ impl AsRef<[u8]> for Packet {
 impl Debug for Packet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Packet")
-            .field("bytes", &self.bytes)
+            .field("bytes", &&self.bytes[..self.len])
+            .field("pos", &self.pos)
             .finish()
     }
 }
@@ -145,18 +192,15 @@ impl Packet {
     }
 
     pub fn g1_alt1(&mut self) -> Result<u8, PacketError> {
-        self.pos += 1;
-        Ok(self.bytes[self.pos - 1] - 128 & 255)
+        g!(self, 1, |[value]: [u8; 1]| value.wrapping_sub(128))
     }
 
     pub fn g1_alt2(&mut self) -> Result<u8, PacketError> {
-        self.pos += 1;
-        Ok(!self.bytes[self.pos - 1] & 255)
+        g!(self, 1, |[value]: [u8; 1]| !value)
     }
 
     pub fn g1_alt3(&mut self) -> Result<u8, PacketError> {
-        self.pos += 1;
-        Ok((128 - self.bytes[self.pos - 1]) & 255)
+        g!(self, 1, |[value]: [u8; 1]| 128u8.wrapping_sub(value))
     }
 
     /// Attempts to return a signed short from the reader, incrementing the position by `2` if successful. Otherwise
@@ -172,28 +216,21 @@ impl Packet {
     }
 
     pub fn g2_alt1(&mut self) -> Result<u16, PacketError> {
-        self.pos += 2;
-        Ok(self.bytes[self.pos - 1] as u16 & 255 << 8 | self.bytes[self.pos - 2] as u16 & 255)
+        g!(self, 2, u16::from_le_bytes)
     }
 
     pub fn g2_alt2(&mut self) -> Result<u16, PacketError> {
-        self.pos += 2;
-        Ok((self.bytes[self.pos - 2] as u16 & 255 << 8)
-            | (self.bytes[self.pos - 1] as u16 - 128 & 255))
+        g!(self, 2, |[high, low]: [u8; 2]| (high as u16) << 8 | low.wrapping_sub(128) as u16)
     }
 
     pub fn g2_alt3(&mut self) -> Result<u16, PacketError> {
-        self.pos += 2;
-        Ok((self.bytes[self.pos - 2] as u16 - 128 & 255) | (self.bytes[self.pos - 1] as u16) << 8)
+        g!(self, 2, |[low, high]: [u8; 2]| low.wrapping_sub(128) as u16 | (high as u16) << 8)
     }
 
     /// Attempts to return a 24-bit unsigned integer from the reader, incrementing the position by
     /// `3` if successful. Otherwise, an error is returned if not enough bytes remain.
     pub fn g3(&mut self) -> Result<usize, PacketError> {
-        self.pos += 3;
-        Ok((self.bytes[self.pos - 3] as usize) << 16
-            | (self.bytes[self.pos - 2] as usize) << 8
-            | self.bytes[self.pos - 1] as usize)
+        g!(self, 3, |[high, mid, low]: [u8; 3]| (high as usize) << 16 | (mid as usize) << 8 | low as usize)
     }
 
     /// Attempts to return a signed integer from the reader, incrementing the position by
@@ -220,13 +257,14 @@ impl Packet {
         g!(self, 8, u64::from_be_bytes)
     }
 
-    /// Performs a conditional "smart" read, returning a signed short or unsigned byte depending on
-    /// the value of the next readable byte and increasing the position based on the literal type read.
-    /// Otherwise, an error is returned if not enough bytes remain.
+    /// Performs a conditional "smart" read, returning an unsigned short with the high bit masked
+    /// off or an unsigned byte depending on the value of the next readable byte and increasing
+    /// the position based on the literal type read. Otherwise, an error is returned if not enough
+    /// bytes remain.
     pub fn gsmart_u16(&mut self) -> Result<usize, PacketError> {
         if let Some(next) = self.peek() {
             if next > 127 {
-                return self.g2s().map(|value| value as usize - 32768);
+                return self.g2().map(|value| value as usize - 32768);
             }
             return self.g1().map(|value| value as usize);
         }
@@ -236,17 +274,58 @@ impl Packet {
     /// Similar to [gsmart_u16](Packet::gsmart_u16), performs a conditional "smart" read, returning
     /// a signed short or signed int depending on the value of the next readable byte and
     /// increasing the position based on the literal type read. Otherwise, an error is returned
-    /// if not enough bytes remain.
+    /// if not enough bytes remain. Negative values are returned in two's complement, as written
+    /// by [psmart_u32](Packet::psmart_u32).
     pub fn gsmart_u32(&mut self) -> Result<u32, PacketError> {
         if let Some(next) = self.peek() {
             if next & 0x80 == 0 {
-                return self.g2s().map(|value| value as u32 - 16384);
+                return self.g2().map(|value| (value as u32).wrapping_sub(16384));
             }
-            return self.g4s().map(|value| value as u32 - 1073741824);
+            return self.g4().map(|value| (value & 0x7FFFFFFF).wrapping_sub(1073741824));
         }
         error("expected at least one byte for get_smart but none were available.".to_string())
     }
 
+    /// Reads a signed "smart", being an unsigned byte offset by `64` if the next readable byte
+    /// is below `128`, otherwise an unsigned short offset by `49152`. An error is returned if
+    /// not enough bytes remain.
+    pub fn gsmart_s16(&mut self) -> Result<i32, PacketError> {
+        match self.peek() {
+            Some(next) if next < 128 => self.g1().map(|value| value as i32 - 64),
+            Some(_) => self.g2().map(|value| value as i32 - 49152),
+            None => error("expected at least one byte for get_smart but none were available.".to_string()),
+        }
+    }
+
+    /// Reads an unsigned "big smart", being an unsigned short if the high bit of the next
+    /// readable byte is clear, otherwise an unsigned int with the high bit masked off. An error
+    /// is returned if not enough bytes remain.
+    pub fn gbig_smart(&mut self) -> Result<u32, PacketError> {
+        match self.peek() {
+            Some(next) if next & 0x80 != 0 => self.g4().map(|value| value & 0x7FFFFFFF),
+            Some(_) => self.g2().map(|value| value as u32),
+            None => error("expected at least one byte for get_big_smart but none were available.".to_string()),
+        }
+    }
+
+    /// Reads a variable length integer as used by MIDI files, where every byte holds 7 bits of
+    /// the value from most to least significant, and has its high bit set if more bytes follow.
+    /// An error is returned if not enough bytes remain or the value exceeds 32 bits.
+    pub fn gvar_u32(&mut self) -> Result<u32, PacketError> {
+        let mut value = 0u32;
+        for _ in 0..5 {
+            let byte = self.g1()?;
+            if value >> 25 != 0 {
+                return error("variable length integer exceeds 32 bits.".to_string());
+            }
+            value = value << 7 | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        error("variable length integer exceeds 5 bytes.".to_string())
+    }
+
     /// Tries to read a null-terminated string (c-string) from the reader, returning an error if the
     /// operation could not complete. The reader position is incremented based on the width of the
     /// string read.
@@ -254,7 +333,7 @@ impl Packet {
         use encoding_rs::WINDOWS_1252;
         use memchr::memchr;
 
-        if let Some(null_pos) = memchr(0, &self.bytes[self.pos..]) {
+        if let Some(null_pos) = memchr(0, &self.bytes[self.pos..self.len]) {
             let end = self.pos + null_pos;
             let slice = &self.bytes[self.pos..end];
             let (string, _, had_errors) = WINDOWS_1252.decode(slice);
@@ -282,6 +361,93 @@ impl Packet {
         Ok(())
     }
 
+    /// Sets the position `offset` bytes before the end of the readable contents, as used to
+    /// read the trailers many cache formats end with.
+    pub fn seek_from_end(&mut self, offset: usize) -> Result<(), PacketError> {
+        match self.len.checked_sub(offset) {
+            Some(index) => self.set_pos(index),
+            None => error(format!(
+                "Invalid position. Offset {} from the end is > len {}",
+                offset, self.len
+            )),
+        }
+    }
+
+    /// Runs `f` with the position set to `index`, restoring the previous position afterwards
+    /// whether or not `f` succeeds.
+    pub fn with_pos<T>(
+        &mut self,
+        index: usize,
+        f: impl FnOnce(&mut Packet) -> Result<T, PacketError>,
+    ) -> Result<T, PacketError> {
+        let pos = self.pos;
+        self.set_pos(index)?;
+        let result = f(self);
+        self.pos = pos;
+        result
+    }
+
+    /// Returns the index the next read starts from.
+    pub fn reader_index(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the index the next write starts from, being the reader index in
+    /// [IndexMode::Shared] and the end of the readable bytes in [IndexMode::Split].
+    pub fn writer_index(&self) -> usize {
+        match self.mode {
+            IndexMode::Shared => self.pos,
+            IndexMode::Split => self.len,
+        }
+    }
+
+    /// Sets the reader index, which may not exceed the end of the readable bytes.
+    pub fn set_reader_index(&mut self, index: usize) -> Result<(), PacketError> {
+        self.set_pos(index)
+    }
+
+    /// Sets the writer index, which in [IndexMode::Split] may not precede the reader index nor
+    /// exceed the underlying buffer. In [IndexMode::Shared] this is the same as
+    /// [set_pos](Packet::set_pos).
+    pub fn set_writer_index(&mut self, index: usize) -> Result<(), PacketError> {
+        match self.mode {
+            IndexMode::Shared => self.set_pos(index),
+            IndexMode::Split => {
+                if index < self.pos || index > self.bytes.len() {
+                    return error(format!(
+                        "Invalid writer index. Index {} is outside {}..={}",
+                        index,
+                        self.pos,
+                        self.bytes.len()
+                    ));
+                }
+                self.len = index;
+                Ok(())
+            }
+        }
+    }
+
+    /// Makes everything written so far readable from the start, ending the readable bytes at
+    /// the writer index and moving the reader index to `0`.
+    pub fn flip(&mut self) {
+        self.len = self.writer_index();
+        self.pos = 0;
+    }
+
+    /// Saves the current reader and writer index to later be restored by
+    /// [reset](Packet::reset).
+    pub fn mark(&mut self) {
+        self.marked = (self.pos, self.len);
+    }
+
+    /// Restores the reader and writer index saved by the last [mark](Packet::mark), or `0` for
+    /// both if never marked. Bytes written since are discarded from the readable bytes.
+    pub fn reset(&mut self) {
+        let (pos, len) = self.marked;
+        self.len = len.min(self.bytes.len());
+        self.pos = pos.min(self.len);
+    }
+
     /// Returns an optional value for the next byte available without incrementing the buffer's position, otherwise returning `None`.
     pub fn peek(&self) -> Option<u8> {
         if self.available_count() == 0 {
@@ -303,29 +469,24 @@ impl Packet {
         self.pos
     }
 
-    /// Returns `true` if no readable bytes remain. Shorthand for `self.remaining() == 0`.
+    /// Returns `true` if no readable bytes remain. Shorthand for `self.available_count() == 0`.
     pub fn is_empty(&self) -> bool {
-        self.pos >= self.bytes.len()
+        self.pos >= self.len
     }
 
     /// Returns the amount of bytes available in the current packet which is determined by
-    /// the following calculation: `capacity - pos`.
+    /// the following calculation: `len - pos`.
     ///
     /// # Safety
     ///
     /// If an overflow were to occur then a value of `None` is returned indicating so. Otherwise,
     /// a success value of `Some(n)` is returned where `n` is the amount of bytes available.
     pub fn available(&self) -> Option<usize> {
-        let (value, overflowed) = self.len().overflowing_sub(self.pos);
-        if overflowed {
-            return None;
-        }
-
-        Some(value)
+        self.len.checked_sub(self.pos)
     }
 
     /// Returns the amount of bytes available in the current packet which is determined by
-    /// the following calculation: `capacity - pos`. Unlike [available](Packet::available),
+    /// the following calculation: `len - pos`. Unlike [available](Packet::available),
     /// failure to obtain the amount of bytes, or if overflow were to occur, then a value of
     /// `0` is returned.
     pub fn available_count(&self) -> usize {
@@ -351,15 +512,33 @@ impl Read for Packet {
     }
 }
 
+/// Seeks within the readable contents of the packet, failing for positions before the start
+/// or past the end.
+impl Seek for Packet {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => (0, i64::try_from(offset).unwrap_or(i64::MAX)),
+            SeekFrom::End(offset) => (self.len, offset),
+            SeekFrom::Current(offset) => (self.pos, offset),
+        };
+        match (base as i64).checked_add(offset) {
+            Some(index) if (0..=self.len as i64).contains(&index) => {
+                self.pos = index as usize;
+                Ok(self.pos as u64)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid seek to {:?} with len {}", pos, self.len),
+            )),
+        }
+    }
+}
+
 impl Iterator for Packet {
     type Item = u8;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Ok(byte) = self.g1() {
-            Some(byte)
-        } else {
-            None
-        }
+        self.g1().ok()
     }
 }
 
@@ -371,22 +550,15 @@ impl Packet {
     }
 
     pub fn p1_alt1(&mut self, value: u8) {
-        self.ensure_capacity(1);
-        self.bytes[self.pos] = value + 128;
-        self.pos += 1;
-        if self.pos > self.len { self.len = self.pos; }
+        self.write_at_cursor(&[value.wrapping_add(128)]);
     }
 
     pub fn p1_alt2(&mut self, value: u8) {
-        self.ensure_capacity(1);
-        self.pos += 1;
-        self.bytes[self.pos - 1] = !value;
+        self.write_at_cursor(&[!value]);
     }
 
     pub fn p1_alt3(&mut self, value: usize) {
-        self.ensure_capacity(1);
-        self.pos += 1;
-        self.bytes[self.pos - 1] = (128 - value) as u8
+        self.write_at_cursor(&[128u8.wrapping_sub(value as u8)]);
     }
 
     /// Writes a signed byte value into the buffer, incrementing the position by `1`.
@@ -408,34 +580,19 @@ impl Packet {
     }
 
     pub fn p2_alt1(&mut self, value: u16) {
-        self.ensure_capacity(2);
-        self.pos += 2;
-        self.bytes[self.pos - 2] = value as u8;
-        self.bytes[self.pos - 1] = (value >> 8) as u8;
+        self.write_at_cursor(&[value as u8, (value >> 8) as u8]);
     }
 
     pub fn p2_alt2(&mut self, value: u16) {
-        self.ensure_capacity(2);
-        self.pos += 2;
-        self.bytes[self.pos - 2] = (value >> 8) as u8;
-        self.bytes[self.pos - 1] = (value + 128) as u8;
+        self.write_at_cursor(&[(value >> 8) as u8, (value as u8).wrapping_add(128)]);
     }
 
     pub fn p2_alt3(&mut self, value: u16) {
-        self.ensure_capacity(2);
-        self.pos += 2;
-        self.bytes[self.pos - 2] = (value + 128) as u8;
-        self.bytes[self.pos - 1] = (value >> 8) as u8;
+        self.write_at_cursor(&[(value as u8).wrapping_add(128), (value >> 8) as u8]);
     }
 
     pub fn p3(&mut self, value: u32) {
-        self.ensure_capacity(3);
-        let pos = self.pos;
-        self.bytes[pos] = (value >> 16) as u8;
-        self.bytes[pos + 1] = (value >> 8) as u8;
-        self.bytes[pos + 2] = value as u8;
-        self.pos += 3;
-        if self.pos > self.len { self.len = self.pos; }
+        self.write_at_cursor(&[(value >> 16) as u8, (value >> 8) as u8, value as u8]);
     }
 
     /// Writes a signed int value into the buffer, incrementing the position by `4`.
@@ -472,200 +629,208 @@ impl Packet {
         self.pjstr(value);
     }
 
-    /// Conditionally writes a signed byte if `n <= 127` otherwise writes an unsigned short. The
-    /// position is incremented relative to the type written.
+    /// Conditionally writes an unsigned byte if `n <= 127` otherwise writes an unsigned short
+    /// with the high bit set, as read by [gsmart_u16](Packet::gsmart_u16). Values are truncated
+    /// to 15 bits. The position is incremented relative to the type written.
     pub fn psmart_u16(&mut self, value: usize) {
         if value <= 127 {
-            self.p1s(value as i8);
+            self.p1(value as u8);
         } else {
+            self.p2(value as u16 | 0x8000);
+        }
+    }
+
+    /// Writes a variable length integer as read by [gvar_u32](Packet::gvar_u32), using the
+    /// fewest bytes able to hold the value.
+    pub fn pvar_u32(&mut self, value: u32) {
+        let mut shift = 28;
+        while shift > 0 && value >> shift == 0 {
+            shift -= 7;
+        }
+        while shift > 0 {
+            self.p1((value >> shift) as u8 | 0x80);
+            shift -= 7;
+        }
+        self.p1(value as u8 & 0x7F);
+    }
+
+    /// Writes a signed "smart" as read by [gsmart_s16](Packet::gsmart_s16), using a single byte
+    /// for values within `-64..=63` and an unsigned short otherwise. Values are truncated to 15
+    /// bits.
+    pub fn psmart_s16(&mut self, value: i32) {
+        if (-64..=63).contains(&value) {
+            self.p1((value + 64) as u8);
+        } else {
+            self.p2((value + 49152) as u16);
+        }
+    }
+
+    /// Writes an unsigned "big smart" as read by [gbig_smart](Packet::gbig_smart), using an
+    /// unsigned short for values below `32768` and an unsigned int with the high bit set
+    /// otherwise. Values are truncated to 31 bits.
+    pub fn pbig_smart(&mut self, value: u32) {
+        if value < 0x8000 {
             self.p2(value as u16);
+        } else {
+            self.p4(value | 0x80000000);
         }
     }
 
     /// Writes a value into the packet through the usage of [p2](Packet::p2) or [p4](Packet::p4).
     /// The packing put operation is contingent on the value being written.
     pub fn psmart_u32(&mut self, value: isize) {
-        if value >= -16384 && value <= 16383 {
-            self.p2(value as u16 + 0x4000);
-        } else if value >= 1073741824 && value <= 1073741823 {
-            self.p4s(-2147483648 | (value + 1073741824) as i32);
+        if (-16384..=16383).contains(&value) {
+            self.p2((value + 0x4000) as u16);
+        } else if (-1073741824..=1073741823).contains(&value) {
+            self.p4((value + 1073741824) as u32 | 0x80000000);
         }
     }
 
+    /// Enciphers every whole 8-byte block of the buffer in place using XTEA with the given key.
     pub fn tiny_key_encrypt(&mut self, key: &[i32; 4]) -> Result<(), PacketError> {
-        let block_count = self.bytes.len() / 8;
-        self.pos = 0;
-
-        for _ in 0..block_count {
-            let mut v0 = self.g4s()?;
-            let mut v1 = self.g4s()?;
-            let mut sum = 0i32;
-            let delta = -1640531527i32;
-
-            for _ in 0..32 {
-                v1 = v1.wrapping_add(
-                    (v0 << 4 ^ v0 >> 5).wrapping_add(v0)
-                        ^ key[((sum >> 11) & 3) as usize].wrapping_add(sum),
-                );
-                sum = sum.wrapping_add(delta);
-                v0 = v0.wrapping_add(
-                    (v1 << 4 ^ v1 >> 5).wrapping_add(v1)
-                        ^ key[(sum & 3) as usize].wrapping_add(sum),
-                );
-            }
-
-            self.pos -= 8;
-            self.p4(v0.try_into().unwrap());
-            self.p4(v1.try_into().unwrap());
-        }
+        let end = self.len;
+        self.tiny_key_encrypt_range(key, 0, end)?;
+        self.pos = end - end % 8;
         Ok(())
     }
 
+    /// Deciphers every whole 8-byte block of the buffer in place using XTEA with the given key.
     pub fn tiny_key_decrypt(&mut self, key: &[i32; 4]) -> Result<(), PacketError> {
-        let block_count = self.bytes.len() / 8;
-        self.pos = 0;
-
-        for _ in 0..block_count {
-            let mut v0 = self.g4s()?;
-            let mut v1 = self.g4s()?;
-            let delta = -1640531527i32;
-            let mut sum = delta.wrapping_mul(32);
-
-            for _ in 0..32 {
-                v0 = v0.wrapping_sub(
-                    (v1 << 4 ^ v1 >> 5).wrapping_add(v1)
-                        ^ key[(sum & 3) as usize].wrapping_add(sum),
-                );
-                sum = sum.wrapping_sub(delta);
-                v1 = v1.wrapping_sub(
-                    (v0 << 4 ^ v0 >> 5).wrapping_add(v0)
-                        ^ key[((sum >> 11) & 3) as usize].wrapping_add(sum),
-                );
-            }
-
-            self.pos -= 8;
-            self.p4(v0.try_into().unwrap());
-            self.p4(v1.try_into().unwrap());
-        }
+        let end = self.len;
+        self.tiny_key_decrypt_range(key, 0, end)?;
+        self.pos = end - end % 8;
         Ok(())
     }
 
+    /// Enciphers the whole 8-byte blocks within `start..end` in place using XTEA with the given
+    /// key. Any trailing bytes that do not fill a block are left untouched and the position is
+    /// restored once complete.
     pub fn tiny_key_encrypt_range(
         &mut self,
         key: &[i32; 4],
         start: usize,
         end: usize,
     ) -> Result<(), PacketError> {
-        let original_pos = self.pos;
-        self.pos = start;
-
-        let block_count = (end - start) / 8;
-        for _ in 0..block_count {
-            let mut v0 = self.g4s()?;
-            let mut v1 = self.g4s()?;
-            let mut sum = 0i32;
-            let delta = -1640531527i32;
-
-            for _ in 0..32 {
-                v1 = v1.wrapping_add(
-                    (v0 << 4 ^ v0 >> 5).wrapping_add(v0)
-                        ^ key[((sum >> 11) & 3) as usize].wrapping_add(sum),
-                );
-                sum = sum.wrapping_add(delta);
-                v0 = v0.wrapping_add(
-                    (v1 << 4 ^ v1 >> 5).wrapping_add(v1)
-                        ^ key[(sum & 3) as usize].wrapping_add(sum),
-                );
-            }
-
-            self.pos -= 8;
-            self.p4(v0.try_into().unwrap());
-            self.p4(v1.try_into().unwrap());
-        }
-
-        self.pos = original_pos;
-        Ok(())
+        self.tiny_key_apply(start, end, |v0, v1| xtea_encipher(v0, v1, key))
     }
 
+    /// Deciphers the whole 8-byte blocks within `start..end` in place using XTEA with the given
+    /// key. Any trailing bytes that do not fill a block are left untouched and the position is
+    /// restored once complete.
     pub fn tiny_key_decrypt_range(
         &mut self,
         key: &[i32; 4],
         start: usize,
         end: usize,
+    ) -> Result<(), PacketError> {
+        self.tiny_key_apply(start, end, |v0, v1| xtea_decipher(v0, v1, key))
+    }
+
+    fn tiny_key_apply(
+        &mut self,
+        start: usize,
+        end: usize,
+        cipher: impl Fn(u32, u32) -> (u32, u32),
     ) -> Result<(), PacketError> {
         let original_pos = self.pos;
         self.pos = start;
 
-        let block_count = (end - start) / 8;
-        let delta = -1640531527i32;
-
+        let block_count = end.saturating_sub(start) / 8;
         for _ in 0..block_count {
-            let mut v0 = self.g4s()?;
-            let mut v1 = self.g4s()?;
-            let mut sum = delta.wrapping_mul(32);
-
-            for _ in 0..32 {
-                v0 = v0.wrapping_sub(
-                    (v1 << 4 ^ v1 >> 5).wrapping_add(v1)
-                        ^ key[(sum & 3) as usize].wrapping_add(sum),
-                );
-                sum = sum.wrapping_sub(delta);
-                v1 = v1.wrapping_sub(
-                    (v0 << 4 ^ v0 >> 5).wrapping_add(v0)
-                        ^ key[((sum >> 11) & 3) as usize].wrapping_add(sum),
-                );
-            }
+            let v0 = self.g4()?;
+            let v1 = self.g4()?;
+            let (v0, v1) = cipher(v0, v1);
 
-            self.pos -= 8;
-            self.p4(v0.try_into().unwrap());
-            self.p4(v1.try_into().unwrap());
+            let block = self.pos - 8;
+            self.bytes[block..block + 4].copy_from_slice(&v0.to_be_bytes());
+            self.bytes[block + 4..self.pos].copy_from_slice(&v1.to_be_bytes());
         }
 
         self.pos = original_pos;
         Ok(())
     }
 
+    /// Encrypts the bytes written so far (`0..writer_index`) with the given RSA key, replacing the contents
+    /// of the packet with the length of the encrypted block as a `u16` followed by the block.
     pub fn rsa_encrypt(&mut self, exponent: &BigInt, modulus: &BigInt) {
-        let input_len = self.pos;
-        self.pos = 0;
+        let encrypted = self.rsa_apply(0..self.writer_index(), exponent, modulus);
+        self.bytes.clear();
+        self.clear();
+        self.p2(encrypted.len() as u16);
+        self.write_at_cursor(&encrypted);
+    }
 
-        let data = self.gdata(input_len);
-        let bigint = BigInt::from_signed_bytes_be(&data);
-        let result = bigint.modpow(exponent, modulus);
-        let encrypted = result.to_signed_bytes_be();
+    /// Like [Packet::rsa_encrypt], but prefixes the encrypted block with its length as a `u8`, as
+    /// done by older revisions. The key must therefore be no larger than 2040 bits.
+    pub fn rsa_encrypt_g1(&mut self, exponent: &BigInt, modulus: &BigInt) {
+        let encrypted = self.rsa_apply(0..self.writer_index(), exponent, modulus);
+        self.bytes.clear();
+        self.clear();
+        self.p1(encrypted.len() as u8);
+        self.write_at_cursor(&encrypted);
+    }
 
-        self.pos = 0;
-        self.p2(encrypted.len() as u16);
-        self.pdata(&encrypted);
+    /// Reads a block prefixed with its length as a `u16` at the current `pos` and decrypts it with
+    /// the given RSA key, replacing the contents of the packet with the decrypted bytes.
+    pub fn rsa_decrypt(&mut self, exponent: &BigInt, modulus: &BigInt) -> Result<(), PacketError> {
+        let len = self.g2()? as usize;
+        self.rsa_decrypt_block(len, exponent, modulus)
     }
 
-    fn write_at_cursor(&mut self, value: &[u8]) {
-        let slice_len = value.len();
-        if self.bytes.capacity() < self.pos + slice_len {
-            self.bytes.resize(self.pos + slice_len, 0);
+    /// Like [Packet::rsa_decrypt], but reads the length of the block as a `u8`.
+    pub fn rsa_decrypt_g1(&mut self, exponent: &BigInt, modulus: &BigInt) -> Result<(), PacketError> {
+        let len = self.g1()? as usize;
+        self.rsa_decrypt_block(len, exponent, modulus)
+    }
+
+    fn rsa_decrypt_block(&mut self, len: usize, exponent: &BigInt, modulus: &BigInt) -> Result<(), PacketError> {
+        if !self.has_available(len) {
+            return error(format!("Not enough data for rsa block of {} bytes.", len));
         }
 
-        self.bytes[self.pos..self.pos + slice_len].copy_from_slice(value);
-        self.pos += slice_len;
+        let decrypted = self.rsa_apply(self.pos..self.pos + len, exponent, modulus);
+        self.bytes = decrypted;
+        self.pos = 0;
+        self.len = self.bytes.len();
+        Ok(())
+    }
+
+    fn rsa_apply(&self, range: Range<usize>, exponent: &BigInt, modulus: &BigInt) -> Vec<u8> {
+        BigInt::from_signed_bytes_be(&self.bytes[range])
+            .modpow(exponent, modulus)
+            .to_signed_bytes_be()
+    }
 
-        if self.pos > self.len {
-            self.len = self.pos;
+    fn write_at_cursor(&mut self, value: &[u8]) {
+        p!(self, value)
+    }
+
+    /// Moves the writer index past `count` bytes just written, extending the readable bytes to
+    /// cover them.
+    pub(crate) fn advance_writer(&mut self, count: usize) {
+        match self.mode {
+            IndexMode::Shared => {
+                self.pos += count;
+                if self.pos > self.len {
+                    self.len = self.pos;
+                }
+            }
+            IndexMode::Split => self.len += count,
         }
     }
 
     /// Increases the capacity of the underlying buffer to be capable of storing at least `new_cap`
-    /// amount of items.
+    /// amount of items, initializing the new storage with 0. The length is left unchanged.
     pub fn grow(&mut self, new_cap: usize) {
-        let old_cap = self.bytes.capacity();
-        if new_cap > old_cap {
-            self.bytes.reserve(new_cap - old_cap);
+        if new_cap > self.bytes.len() {
+            self.bytes.resize(new_cap, 0);
         }
     }
 
     /// Verifies if enough space exists within the underlying buffer, expanding the buffer
     /// if necessary.
-    fn ensure_capacity(&mut self, space_needed: usize) {
-        let required_len = self.pos + space_needed;
+    pub(crate) fn ensure_capacity(&mut self, space_needed: usize) {
+        let required_len = self.writer_index() + space_needed;
         if required_len > self.bytes.len() {
             self.bytes.resize(required_len, 0);
         }
@@ -674,13 +839,13 @@ impl Packet {
     /// Returns the capacity of the underlying buffer denoting the amount of items the buffer is
     /// capable of holding before needing to be resized.
     pub fn capacity(&self) -> usize {
-        self.bytes.capacity()
+        self.bytes.len()
     }
 
-    /// Returns the total amount of bytes contained in the underlying buffer, not accounting for the
-    /// current position within the buffer.
+    /// Returns the amount of bytes written to the packet, not accounting for the current
+    /// position within the buffer.
     pub fn len(&self) -> usize {
-        self.bytes.len()
+        self.len
     }
 
     /// Returns a slice of the packet's contents returning a partial view over the contents of this
@@ -692,8 +857,10 @@ impl Packet {
     /// Appends a slice onto the end of the packet's contents. The `pos` of the cursor remains
     /// the same.
     pub fn append_slice(&mut self, slice: &[u8]) {
-        self.bytes.extend_from_slice(slice);
-        self.len = self.bytes.len();
+        let end = self.len + slice.len();
+        self.grow(end);
+        self.bytes[self.len..end].copy_from_slice(slice);
+        self.len = end;
     }
 
     pub fn compact(&mut self) {
@@ -708,42 +875,41 @@ impl Packet {
 
         self.bytes.copy_within(self.pos..self.len, 0);
         self.len -= self.pos;
+        self.marked = (self.marked.0.saturating_sub(self.pos), self.marked.1.saturating_sub(self.pos));
         self.pos = 0;
     }
 
     /// Reads a series of bytes from this packet returning a byte array containing the contents
     /// read in the form of `Vec<u8>`. The contents being read starts from the current `pos`
-    /// and reads up to `len` bytes. If the amount of bytes attempting to be read exceed the
-    /// overall length of the packet (where `pos + len >= len`) then the `len` is clamped to
-    /// `bytes.len()` instead to avoid access invalid memory. The `pos` is increased based on the
-    /// amount of bytes written.
+    /// and reads up to `len` bytes. If fewer than `len` bytes are available then only the
+    /// available bytes are returned. The `pos` is increased based on the amount of bytes read.
     pub fn gdata(&mut self, len: usize) -> Vec<u8> {
-        let end = min(self.pos + len, self.bytes.len());
+        let end = min(self.pos + len, self.len);
         let data = self.bytes[self.pos..end].to_vec();
-        self.pos += len;
+        self.pos = end;
         data
     }
 
-    /// Writes a slice to this packet, inserting the passed-in data starting at the current `pos`.
-    /// If the length of the data attempting to be written ends up exceeding the overall length
-    /// of the packet then the length is clamped to the packet length to avoid writing to invalid
-    /// memory. The `pos` is increased based on the amount of bytes written.
+    /// Writes a slice to this packet at the writer index, growing the buffer if necessary. The
+    /// writer index is increased based on the amount of bytes written.
     pub fn pdata(&mut self, data: &[u8]) {
-        let bytes_to_write = min(data.len(), self.available_count());
-        if bytes_to_write > 0 {
-            let end = self.pos + bytes_to_write;
-            self.bytes[self.pos..end].copy_from_slice(&data[..bytes_to_write]);
-            self.pos += bytes_to_write;
-        }
+        self.write_at_cursor(data);
     }
 
+    /// Overwrites the bytes within `range` with `data`, growing the buffer and extending the
+    /// length if the range ends past them. Only as much of `data` as fits in the range is
+    /// written, and the indices are left unchanged.
     pub fn pdata_at(&mut self, data: &[u8], range: impl Into<RangeInclusive<usize>>) {
         let indices = range.into();
-        let end = min(*indices.end(), self.bytes.len());
-        self.bytes.splice(self.pos..end, data.iter().cloned());
-
-        let wrote = end - self.pos;
-        self.pos += wrote;
+        let start = *indices.start();
+        let count = min(data.len(), (indices.end() + 1).saturating_sub(start));
+        if count == 0 {
+            return;
+        }
+        let end = start + count;
+        self.grow(end);
+        self.bytes[start..end].copy_from_slice(&data[..count]);
+        self.len = self.len.max(end);
     }
 
     /// Allocates an array capable of holding the copied contents of this writer.
@@ -751,19 +917,18 @@ impl Packet {
         self.bytes[self.pos..self.len].to_vec()
     }
 
+    /// Sets the amount of bytes written, growing the buffer if necessary and moving the reader
+    /// index back if it would otherwise exceed the new length.
     pub fn set_len(&mut self, len: usize) {
-        if len > self.bytes.len() {
-            self.bytes.resize(len, 0);
-        }
+        self.grow(len);
         self.len = len;
+        self.pos = min(self.pos, len);
     }
 }
 
 impl Write for Packet {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        for byte in buf {
-            self.p1(*byte);
-        }
+        p!(self, buf);
         Ok(buf.len())
     }
 
@@ -771,3 +936,36 @@ impl Write for Packet {
         Ok(())
     }
 }
+
+const XTEA_DELTA: u32 = 0x9E3779B9;
+const XTEA_ROUNDS: u32 = 32;
+
+fn xtea_encipher(mut v0: u32, mut v1: u32, key: &[i32; 4]) -> (u32, u32) {
+    let mut sum = 0u32;
+    for _ in 0..XTEA_ROUNDS {
+        v0 = v0.wrapping_add(
+            ((v1 << 4 ^ v1 >> 5).wrapping_add(v1)) ^ sum.wrapping_add(key[(sum & 3) as usize] as u32),
+        );
+        sum = sum.wrapping_add(XTEA_DELTA);
+        v1 = v1.wrapping_add(
+            ((v0 << 4 ^ v0 >> 5).wrapping_add(v0))
+                ^ sum.wrapping_add(key[((sum >> 11) & 3) as usize] as u32),
+        );
+    }
+    (v0, v1)
+}
+
+fn xtea_decipher(mut v0: u32, mut v1: u32, key: &[i32; 4]) -> (u32, u32) {
+    let mut sum = XTEA_DELTA.wrapping_mul(XTEA_ROUNDS);
+    for _ in 0..XTEA_ROUNDS {
+        v1 = v1.wrapping_sub(
+            ((v0 << 4 ^ v0 >> 5).wrapping_add(v0))
+                ^ sum.wrapping_add(key[((sum >> 11) & 3) as usize] as u32),
+        );
+        sum = sum.wrapping_sub(XTEA_DELTA);
+        v0 = v0.wrapping_sub(
+            ((v1 << 4 ^ v1 >> 5).wrapping_add(v1)) ^ sum.wrapping_add(key[(sum & 3) as usize] as u32),
+        );
+    }
+    (v0, v1)
+}
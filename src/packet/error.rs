@@ -1,4 +1,4 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
 #[derive(Debug)]
 pub enum PacketError {
@@ -13,5 +13,5 @@ impl From<Error> for PacketError {
 }
 
 pub(crate) fn error<T>(reason: String) -> Result<T, PacketError> {
-    Err(PacketError::Io(Error::new(ErrorKind::Other, reason)))
+    Err(PacketError::Io(Error::other(reason)))
 }
\ No newline at end of file
@@ -0,0 +1,339 @@
+use crate::cache::container::Container;
+use crate::packet::bits::{BitReader, BitWriter};
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// The width of the palette sent in [RebuildDynamic], in zones.
+pub const PALETTE_SIZE: usize = 13;
+
+/// The amount of planes in the palette sent in [RebuildDynamic].
+pub const PALETTE_PLANES: usize = 4;
+
+/// Returns the id of the region containing the given zone, where a zone is an 8x8 tile chunk
+/// and a region is an 8x8 zone area.
+pub fn region_id(zone_x: u16, zone_y: u16) -> u16 {
+    (zone_x >> 3) << 8 | (zone_y >> 3)
+}
+
+/// The XTEA keys landscape files are encrypted with, by region id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XteaKeyStore {
+    keys: BTreeMap<u16, [i32; 4]>,
+}
+
+impl XteaKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, region: u16, key: [i32; 4]) {
+        self.keys.insert(region, key);
+    }
+
+    pub fn get(&self, region: u16) -> Option<[i32; 4]> {
+        self.keys.get(&region).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u16, [i32; 4])> + '_ {
+        self.keys.iter().map(|(region, key)| (*region, *key))
+    }
+
+    /// Parses keys from text holding one region per line, the region id followed by the four
+    /// key parts separated by any non-numeric characters, such as `12850: 1, 2, 3, 4`. Blank
+    /// lines and lines starting with `#` are ignored.
+    pub fn parse_text(text: &str) -> Result<XteaKeyStore, PacketError> {
+        let mut store = XteaKeyStore::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match parse_ints(line)?.as_slice() {
+                [region, a, b, c, d] => store.insert(*region as u16, [*a, *b, *c, *d]),
+                _ => return error(format!("Expected a region and four key parts in '{}'.", line)),
+            }
+        }
+        Ok(store)
+    }
+
+    /// Loads keys from a directory holding a `{region}.txt` file per region, each holding the
+    /// four key parts. Other files are ignored.
+    pub fn load_dir(path: impl AsRef<Path>) -> Result<XteaKeyStore, PacketError> {
+        let mut store = XteaKeyStore::new();
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("txt") {
+                continue;
+            }
+            let region = match path.file_stem().and_then(|stem| stem.to_str()?.parse::<u16>().ok()) {
+                Some(region) => region,
+                None => continue,
+            };
+
+            match parse_ints(&fs::read_to_string(&path)?)?.as_slice() {
+                [a, b, c, d] => store.insert(region, [*a, *b, *c, *d]),
+                _ => return error(format!("Expected four key parts in {}.", path.display())),
+            }
+        }
+        Ok(store)
+    }
+
+    /// Parses keys from JSON, either an array of objects holding the region under `region`,
+    /// `mapsquare` or `id` and the key under `keys` or `key`, or an object mapping region ids to
+    /// keys.
+    #[cfg(feature = "json")]
+    pub fn parse_json(json: &str) -> Result<XteaKeyStore, PacketError> {
+        use serde_json::Value;
+
+        fn parse_key(value: Option<&Value>) -> Result<[i32; 4], PacketError> {
+            let parts: Option<Vec<i32>> = value
+                .and_then(Value::as_array)
+                .map(|parts| parts.iter().map(|part| part.as_i64().map(|part| part as i32)).collect())
+                .unwrap_or_default();
+            match parts.as_deref() {
+                Some([a, b, c, d]) => Ok([*a, *b, *c, *d]),
+                _ => error("Expected a key of four integers.".to_string()),
+            }
+        }
+
+        let value: Value = match serde_json::from_str(json) {
+            Ok(value) => value,
+            Err(e) => return error(format!("Invalid xtea key json: {}", e)),
+        };
+
+        let mut store = XteaKeyStore::new();
+        match value {
+            Value::Array(entries) => {
+                for entry in entries {
+                    let region = ["region", "mapsquare", "id"]
+                        .iter()
+                        .find_map(|name| entry.get(name).and_then(Value::as_u64));
+                    let key = entry.get("keys").or_else(|| entry.get("key"));
+                    match region {
+                        Some(region) => store.insert(region as u16, parse_key(key)?),
+                        None => return error(format!("Missing region in xtea key entry {}.", entry)),
+                    }
+                }
+            }
+            Value::Object(entries) => {
+                for (region, key) in entries {
+                    match region.parse::<u16>() {
+                        Ok(region) => store.insert(region, parse_key(Some(&key))?),
+                        Err(_) => return error(format!("Invalid region id '{}'.", region)),
+                    }
+                }
+            }
+            _ => return error("Expected an array or object of xtea keys.".to_string()),
+        }
+        Ok(store)
+    }
+
+    /// Decodes the landscape container of the given region starting at the packet's current
+    /// position, decrypting it in place with the region's key if one is known.
+    pub fn decode_landscape(&self, region: u16, packet: &mut Packet) -> Result<Container, PacketError> {
+        Container::decode(packet, self.get(region))
+    }
+}
+
+fn parse_ints(text: &str) -> Result<Vec<i32>, PacketError> {
+    text.split(|c: char| !c.is_ascii_digit() && c != '-')
+        .filter(|part| !part.is_empty())
+        .map(|part| match part.parse::<i64>() {
+            Ok(value) => Ok(value as i32),
+            Err(_) => error(format!("Invalid integer '{}'.", part)),
+        })
+        .collect()
+}
+
+/// Rebuilds the static map around the player.
+///
+/// - `g2` zone x, `g2` zone y, `g2` key count, `g4` x4 per key
+///
+/// A key is sent for every region within view of the given zone, ordered by x and then y, as
+/// returned by [regions](RebuildNormal::regions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebuildNormal {
+    pub zone_x: u16,
+    pub zone_y: u16,
+    pub keys: Vec<[i32; 4]>,
+}
+
+impl RebuildNormal {
+    /// Creates the packet for the given zone, looking up the key of every region in view. Regions
+    /// without a known key are sent an all-zero key.
+    pub fn new(zone_x: u16, zone_y: u16, store: &XteaKeyStore) -> Self {
+        let keys = Self::regions(zone_x, zone_y)
+            .into_iter()
+            .map(|region| store.get(region).unwrap_or_default())
+            .collect();
+        Self { zone_x, zone_y, keys }
+    }
+
+    /// Returns the regions within view of the given zone, in the order their keys are sent.
+    pub fn regions(zone_x: u16, zone_y: u16) -> Vec<u16> {
+        let mut regions = Vec::new();
+        for region_x in zone_x.saturating_sub(6) / 8..=zone_x.saturating_add(6) / 8 {
+            for region_y in zone_y.saturating_sub(6) / 8..=zone_y.saturating_add(6) / 8 {
+                regions.push(region_x << 8 | region_y);
+            }
+        }
+        regions
+    }
+
+    pub fn encode(&self, packet: &mut Packet) {
+        packet.p2(self.zone_x);
+        packet.p2(self.zone_y);
+        encode_keys(packet, &self.keys);
+    }
+
+    pub fn decode(packet: &mut Packet) -> Result<RebuildNormal, PacketError> {
+        Ok(RebuildNormal {
+            zone_x: packet.g2()?,
+            zone_y: packet.g2()?,
+            keys: decode_keys(packet)?,
+        })
+    }
+}
+
+/// A zone copied into the map built by [RebuildDynamic].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaletteZone {
+    pub plane: u8,
+    pub zone_x: u16,
+    pub zone_y: u16,
+    /// The amount of clockwise quarter turns applied to the zone.
+    pub rotation: u8,
+}
+
+impl PaletteZone {
+    pub fn region(&self) -> u16 {
+        region_id(self.zone_x, self.zone_y)
+    }
+
+    fn pack(&self) -> u32 {
+        (self.plane as u32 & 0x3) << 24
+            | (self.zone_x as u32 & 0x3FF) << 14
+            | (self.zone_y as u32 & 0x7FF) << 3
+            | (self.rotation as u32 & 0x3) << 1
+    }
+
+    fn unpack(packed: u32) -> PaletteZone {
+        PaletteZone {
+            plane: (packed >> 24 & 0x3) as u8,
+            zone_x: (packed >> 14 & 0x3FF) as u16,
+            zone_y: (packed >> 3 & 0x7FF) as u16,
+            rotation: (packed >> 1 & 0x3) as u8,
+        }
+    }
+}
+
+/// The zones making up a dynamic map, indexed by `[plane][x][y]`.
+pub type Palette = [[[Option<PaletteZone>; PALETTE_SIZE]; PALETTE_SIZE]; PALETTE_PLANES];
+
+/// Rebuilds the map around the player from a palette of zones, as used for instances.
+///
+/// - `g2` zone x, `g2` zone y
+/// - bit access: for every plane, x and y of the palette, a 1-bit flag followed by the 26-bit
+///   packed zone if set
+/// - `g2` key count, `g4` x4 per key
+///
+/// A key is sent for every distinct region referenced by the palette, in the order the palette
+/// is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebuildDynamic {
+    pub zone_x: u16,
+    pub zone_y: u16,
+    pub palette: Palette,
+    pub keys: Vec<[i32; 4]>,
+}
+
+impl RebuildDynamic {
+    /// Creates the packet for the given zone and palette, looking up the key of every region
+    /// referenced. Regions without a known key are sent an all-zero key.
+    pub fn new(zone_x: u16, zone_y: u16, palette: Palette, store: &XteaKeyStore) -> Self {
+        let keys = Self::regions(&palette)
+            .into_iter()
+            .map(|region| store.get(region).unwrap_or_default())
+            .collect();
+        Self { zone_x, zone_y, palette, keys }
+    }
+
+    /// Returns the distinct regions referenced by the palette, in the order their keys are sent.
+    pub fn regions(palette: &Palette) -> Vec<u16> {
+        let mut regions = Vec::new();
+        for zone in palette.iter().flatten().flatten().flatten() {
+            if !regions.contains(&zone.region()) {
+                regions.push(zone.region());
+            }
+        }
+        regions
+    }
+
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        packet.p2(self.zone_x);
+        packet.p2(self.zone_y);
+        {
+            let mut writer = BitWriter::from(&mut *packet);
+            for zone in self.palette.iter().flatten().flatten() {
+                match zone {
+                    Some(zone) => {
+                        writer.write_bits(1, 1)?;
+                        writer.write_bits(zone.pack(), 26)?;
+                    }
+                    None => writer.write_bits(0, 1)?,
+                }
+            }
+        }
+        encode_keys(packet, &self.keys);
+        Ok(())
+    }
+
+    pub fn decode(packet: &mut Packet) -> Result<RebuildDynamic, PacketError> {
+        let zone_x = packet.g2()?;
+        let zone_y = packet.g2()?;
+
+        let mut palette = [[[None; PALETTE_SIZE]; PALETTE_SIZE]; PALETTE_PLANES];
+        let bits_read = {
+            let mut reader = BitReader::from(&*packet);
+            for zone in palette.iter_mut().flatten().flatten() {
+                if reader.read_bits(1)? == 1 {
+                    *zone = Some(PaletteZone::unpack(reader.read_bits(26)? as u32));
+                }
+            }
+            reader.get_bit_position()
+        };
+        packet.skip(bits_read.div_ceil(8));
+
+        Ok(RebuildDynamic {
+            zone_x,
+            zone_y,
+            palette,
+            keys: decode_keys(packet)?,
+        })
+    }
+}
+
+fn encode_keys(packet: &mut Packet, keys: &[[i32; 4]]) {
+    packet.p2(keys.len() as u16);
+    for key in keys {
+        key.iter().for_each(|part| packet.p4(*part as u32));
+    }
+}
+
+fn decode_keys(packet: &mut Packet) -> Result<Vec<[i32; 4]>, PacketError> {
+    let count = packet.g2()? as usize;
+    (0..count)
+        .map(|_| Ok([packet.g4s()?, packet.g4s()?, packet.g4s()?, packet.g4s()?]))
+        .collect()
+}
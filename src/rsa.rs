@@ -0,0 +1,361 @@
+use crate::packet::error::{error, PacketError};
+use num_bigint::{BigInt, BigUint, Sign};
+
+/// The public exponent used for generated keys.
+const PUBLIC_EXPONENT: u32 = 65537;
+
+/// An RSA public key, as embedded within the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsaPublicKey {
+    pub exponent: BigInt,
+    pub modulus: BigInt,
+}
+
+impl RsaPublicKey {
+    pub fn new(exponent: BigInt, modulus: BigInt) -> Self {
+        Self { exponent, modulus }
+    }
+
+    /// Parses a public key from hexadecimal strings.
+    pub fn from_hex(exponent: &str, modulus: &str) -> Result<RsaPublicKey, PacketError> {
+        let key = RsaPublicKey::new(parse_hex(exponent)?, parse_hex(modulus)?);
+        validate(&key.modulus, &[&key.exponent])?;
+        Ok(key)
+    }
+
+    /// Parses a PEM encoded `PUBLIC KEY` or `RSA PUBLIC KEY`.
+    pub fn from_pem(pem: &str) -> Result<RsaPublicKey, PacketError> {
+        let (label, der) = decode_pem(pem)?;
+        let mut reader = DerReader::new(&der);
+        let mut key = reader.sequence()?;
+        if label == "PUBLIC KEY" {
+            // SubjectPublicKeyInfo: the RSA key is wrapped within a bit string following the
+            // algorithm identifier.
+            key.sequence()?;
+            let bits = key.element(DER_BIT_STRING)?;
+            key = DerReader::new(bits.get(1..).unwrap_or_default()).sequence()?;
+        } else if label != "RSA PUBLIC KEY" {
+            return error(format!("Unsupported PEM label '{}' for an rsa public key.", label));
+        }
+        let modulus = key.integer()?;
+        let exponent = key.integer()?;
+        validate(&modulus, &[&exponent])?;
+        Ok(RsaPublicKey::new(exponent, modulus))
+    }
+
+    /// Encrypts `data`, interpreted as a signed big-endian integer, returning the signed
+    /// big-endian result as the client does.
+    pub fn encrypt(&self, data: &[u8]) -> Vec<u8> {
+        modpow(data, &self.exponent, &self.modulus)
+    }
+}
+
+/// An RSA key pair, used by the server to decrypt the login block sent by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsaKeyPair {
+    pub modulus: BigInt,
+    pub public_exponent: BigInt,
+    pub private_exponent: BigInt,
+}
+
+impl RsaKeyPair {
+    pub fn new(modulus: BigInt, public_exponent: BigInt, private_exponent: BigInt) -> Self {
+        Self {
+            modulus,
+            public_exponent,
+            private_exponent,
+        }
+    }
+
+    /// Parses a key pair from hexadecimal strings.
+    pub fn from_hex(
+        modulus: &str,
+        public_exponent: &str,
+        private_exponent: &str,
+    ) -> Result<RsaKeyPair, PacketError> {
+        let key = RsaKeyPair::new(
+            parse_hex(modulus)?,
+            parse_hex(public_exponent)?,
+            parse_hex(private_exponent)?,
+        );
+        validate(&key.modulus, &[&key.public_exponent, &key.private_exponent])?;
+        Ok(key)
+    }
+
+    /// Parses a PEM encoded `RSA PRIVATE KEY` (PKCS#1) or `PRIVATE KEY` (PKCS#8).
+    pub fn from_pem(pem: &str) -> Result<RsaKeyPair, PacketError> {
+        let (label, der) = decode_pem(pem)?;
+        let mut reader = DerReader::new(&der);
+        let mut key = reader.sequence()?;
+        if label == "PRIVATE KEY" {
+            // PrivateKeyInfo: a version and algorithm identifier followed by the PKCS#1 key
+            // within an octet string.
+            key.integer()?;
+            key.sequence()?;
+            let inner = key.element(DER_OCTET_STRING)?;
+            key = DerReader::new(inner).sequence()?;
+        } else if label != "RSA PRIVATE KEY" {
+            return error(format!("Unsupported PEM label '{}' for an rsa private key.", label));
+        }
+
+        key.integer()?;
+        let modulus = key.integer()?;
+        let public_exponent = key.integer()?;
+        let private_exponent = key.integer()?;
+        validate(&modulus, &[&public_exponent, &private_exponent])?;
+        Ok(RsaKeyPair::new(modulus, public_exponent, private_exponent))
+    }
+
+    /// Deterministically generates a key pair with a modulus of roughly `bits` bits from `seed`.
+    ///
+    /// The generator is not cryptographically secure, and is only intended for producing keys
+    /// for tests and local development.
+    pub fn generate(bits: u64, seed: u64) -> RsaKeyPair {
+        let mut random = SplitMix64(seed);
+        let e = BigUint::from(PUBLIC_EXPONENT);
+        loop {
+            let p = generate_prime(bits / 2, &mut random);
+            let q = generate_prime(bits - bits / 2, &mut random);
+            if p == q {
+                continue;
+            }
+
+            let phi = (&p - 1u32) * (&q - 1u32);
+            if let Some(d) = mod_inverse(&e, &phi) {
+                return RsaKeyPair::new(
+                    BigInt::from(p * q),
+                    BigInt::from(e),
+                    BigInt::from(d),
+                );
+            }
+        }
+    }
+
+    pub fn public_key(&self) -> RsaPublicKey {
+        RsaPublicKey::new(self.public_exponent.clone(), self.modulus.clone())
+    }
+
+    /// Encrypts `data` with the public exponent.
+    pub fn encrypt(&self, data: &[u8]) -> Vec<u8> {
+        modpow(data, &self.public_exponent, &self.modulus)
+    }
+
+    /// Decrypts `data` with the private exponent.
+    pub fn decrypt(&self, data: &[u8]) -> Vec<u8> {
+        modpow(data, &self.private_exponent, &self.modulus)
+    }
+}
+
+fn modpow(data: &[u8], exponent: &BigInt, modulus: &BigInt) -> Vec<u8> {
+    BigInt::from_signed_bytes_be(data)
+        .modpow(exponent, modulus)
+        .to_signed_bytes_be()
+}
+
+/// Rejects keys that would panic when used, being those with a modulus below `2` or a negative
+/// exponent.
+fn validate(modulus: &BigInt, exponents: &[&BigInt]) -> Result<(), PacketError> {
+    if *modulus <= BigInt::from(1) {
+        return error(format!("Invalid rsa modulus {}.", modulus));
+    }
+    if exponents.iter().any(|exponent| exponent.sign() == Sign::Minus) {
+        return error("Rsa exponents must not be negative.".to_string());
+    }
+    Ok(())
+}
+
+fn parse_hex(value: &str) -> Result<BigInt, PacketError> {
+    let value = value.trim();
+    let digits = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value);
+    match BigInt::parse_bytes(digits.as_bytes(), 16) {
+        Some(value) => Ok(value),
+        None => error(format!("Invalid hexadecimal rsa value '{}'.", value)),
+    }
+}
+
+/// Strips the armour from a PEM document, returning its label and decoded contents.
+fn decode_pem(pem: &str) -> Result<(String, Vec<u8>), PacketError> {
+    let mut lines = pem.lines().map(str::trim).skip_while(|line| !line.starts_with("-----BEGIN "));
+    let label = match lines.next() {
+        Some(line) => line.trim_start_matches("-----BEGIN ").trim_end_matches("-----").to_string(),
+        None => return error("Missing PEM header.".to_string()),
+    };
+
+    let mut body = String::new();
+    for line in lines {
+        if line.starts_with("-----END ") {
+            return Ok((label, decode_base64(&body)?));
+        }
+        body.push_str(line);
+    }
+    error("Missing PEM footer.".to_string())
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>, PacketError> {
+    let mut decoded = Vec::with_capacity(value.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in value.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return error(format!("Invalid base64 character '{}'.", c as char)),
+        };
+        buffer = buffer << 6 | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+const DER_INTEGER: u8 = 0x02;
+const DER_BIT_STRING: u8 = 0x03;
+const DER_OCTET_STRING: u8 = 0x04;
+const DER_SEQUENCE: u8 = 0x30;
+
+/// A minimal reader for the subset of DER used by RSA key formats.
+struct DerReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> DerReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8, PacketError> {
+        match self.data.get(self.pos) {
+            Some(byte) => {
+                self.pos += 1;
+                Ok(*byte)
+            }
+            None => error("Unexpected end of DER data.".to_string()),
+        }
+    }
+
+    /// Reads an element with the given tag, returning its contents.
+    fn element(&mut self, tag: u8) -> Result<&'a [u8], PacketError> {
+        let found = self.byte()?;
+        if found != tag {
+            return error(format!("Expected DER tag {:#x} but found {:#x}.", tag, found));
+        }
+
+        let mut len = self.byte()? as usize;
+        if len & 0x80 != 0 {
+            let count = len & 0x7F;
+            if count > std::mem::size_of::<usize>() {
+                return error("DER length is too large.".to_string());
+            }
+            len = 0;
+            for _ in 0..count {
+                len = len << 8 | self.byte()? as usize;
+            }
+        }
+
+        match self.pos.checked_add(len).and_then(|end| self.data.get(self.pos..end)) {
+            Some(contents) => {
+                self.pos += len;
+                Ok(contents)
+            }
+            None => error("DER element extends beyond the end of the data.".to_string()),
+        }
+    }
+
+    fn sequence(&mut self) -> Result<DerReader<'a>, PacketError> {
+        self.element(DER_SEQUENCE).map(DerReader::new)
+    }
+
+    fn integer(&mut self) -> Result<BigInt, PacketError> {
+        self.element(DER_INTEGER).map(BigInt::from_signed_bytes_be)
+    }
+}
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    fn next_bits(&mut self, bits: u64) -> BigUint {
+        let words: Vec<u64> = (0..bits.div_ceil(64)).map(|_| self.next()).collect();
+        let mut value = BigUint::from_slice(
+            &words.iter().flat_map(|word| [*word as u32, (*word >> 32) as u32]).collect::<Vec<_>>(),
+        );
+        let excess = words.len() as u64 * 64 - bits;
+        value >>= excess;
+        value
+    }
+}
+
+const SMALL_PRIMES: [u32; 24] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+fn generate_prime(bits: u64, random: &mut SplitMix64) -> BigUint {
+    loop {
+        let mut candidate = random.next_bits(bits);
+        candidate.set_bit(bits - 1, true);
+        candidate.set_bit(0, true);
+        if is_probable_prime(&candidate, random) {
+            return candidate;
+        }
+    }
+}
+
+fn is_probable_prime(n: &BigUint, random: &mut SplitMix64) -> bool {
+    for prime in SMALL_PRIMES {
+        if n % prime == BigUint::ZERO {
+            return *n == BigUint::from(prime);
+        }
+    }
+
+    let one = BigUint::from(1u32);
+    let n_minus_one = n - 1u32;
+    let shift = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> shift;
+
+    'witness: for _ in 0..32 {
+        let a = random.next_bits(n.bits()) % (n - 3u32) + 2u32;
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..shift {
+            x = x.modpow(&BigUint::from(2u32), n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Computes the inverse of `a` modulo `m` using the extended Euclidean algorithm.
+fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    let (mut old_r, mut r) = (BigInt::from(a.clone()), BigInt::from(m.clone()));
+    let (mut old_s, mut s) = (BigInt::from(1), BigInt::from(0));
+    while r.sign() != Sign::NoSign {
+        let quotient = &old_r / &r;
+        (old_r, r) = (r.clone(), old_r - &quotient * &r);
+        (old_s, s) = (s.clone(), old_s - &quotient * &s);
+    }
+
+    if old_r != BigInt::from(1) {
+        return None;
+    }
+    let m = BigInt::from(m.clone());
+    ((old_s % &m + &m) % &m).to_biguint()
+}
@@ -0,0 +1,178 @@
+use crate::packet::bytes::Packet;
+use crate::packet::error::{error, PacketError};
+
+/// The status byte beginning every world list packet.
+const STATUS_OK: u8 = 1;
+
+/// A country worlds may be located in, referenced by index from each [World].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Country {
+    /// The sprite id of the country's flag.
+    pub flag: u16,
+    pub name: String,
+}
+
+/// A single world and its current population.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct World {
+    pub id: u16,
+    /// The index of the world's [Country] within the list.
+    pub country: u8,
+    pub flags: u32,
+    pub activity: String,
+    pub host: String,
+    pub players: u16,
+}
+
+impl World {
+    pub const MEMBERS: u32 = 0x1;
+    pub const QUICK_CHAT: u32 = 0x2;
+    pub const PVP: u32 = 0x4;
+    pub const LOOT_SHARE: u32 = 0x8;
+
+    pub fn is_members(&self) -> bool {
+        self.flags & World::MEMBERS != 0
+    }
+}
+
+/// The list of worlds served to the client by the lobby.
+///
+/// The list is sent either in full, or as a population update holding only the player count of
+/// every world:
+///
+/// - header: `g1` 1, `g1` full
+/// - full: `gsmart` country count, (`gsmart` flag, `gjstr` name) per country, `gsmart` lowest id,
+///   `gsmart` highest id, `gsmart` world count, (`gsmart` id offset, `g1` country, `g4` flags,
+///   `gjstr` activity, `gjstr` host) per world, `g4` checksum
+/// - population: (`gsmart` id offset, `g2` players) per world until the end of the packet
+///
+/// World ids are sent as offsets from the lowest id in the list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorldList {
+    pub countries: Vec<Country>,
+    pub worlds: Vec<World>,
+    /// Identifies this version of the list. The client sends it back when requesting the list,
+    /// allowing the lobby to reply with a population update if the list has not changed.
+    pub checksum: u32,
+}
+
+impl WorldList {
+    /// Writes the full list, including the population of every world.
+    pub fn encode(&self, packet: &mut Packet) -> Result<(), PacketError> {
+        let base = self.base_id();
+        packet.p1(STATUS_OK);
+        packet.p1(1);
+
+        packet.psmart_u16(self.countries.len());
+        for country in &self.countries {
+            packet.psmart_u16(country.flag as usize);
+            packet.pjstr(&country.name);
+        }
+
+        packet.psmart_u16(base as usize);
+        packet.psmart_u16(self.worlds.iter().map(|world| world.id).max().unwrap_or(0) as usize);
+        packet.psmart_u16(self.worlds.len());
+        for world in &self.worlds {
+            if world.country as usize >= self.countries.len() {
+                return error(format!("World {} has unknown country {}.", world.id, world.country));
+            }
+            packet.psmart_u16((world.id - base) as usize);
+            packet.p1(world.country);
+            packet.p4(world.flags);
+            packet.pjstr(&world.activity);
+            packet.pjstr(&world.host);
+        }
+        packet.p4(self.checksum);
+
+        self.encode_players(packet, base);
+        Ok(())
+    }
+
+    /// Writes a population update, holding only the player count of every world.
+    pub fn encode_population(&self, packet: &mut Packet) {
+        packet.p1(STATUS_OK);
+        packet.p1(0);
+        self.encode_players(packet, self.base_id());
+    }
+
+    /// Decodes a full list, consuming the rest of the packet.
+    pub fn decode(packet: &mut Packet) -> Result<WorldList, PacketError> {
+        let mut list = WorldList::default();
+        if !list.decode_update(packet)? {
+            return error("Expected a full world list but received a population update.".to_string());
+        }
+        Ok(list)
+    }
+
+    /// Applies a full list or population update to this list, consuming the rest of the packet.
+    /// Returns whether the update was a full list.
+    pub fn decode_update(&mut self, packet: &mut Packet) -> Result<bool, PacketError> {
+        let status = packet.g1()?;
+        if status != STATUS_OK {
+            return error(format!("Unexpected world list status {}.", status));
+        }
+
+        let full = packet.g1()? == 1;
+        if full {
+            self.decode_full(packet)?;
+        }
+
+        let base = self.base_id();
+        while packet.available_count() > 0 {
+            let id = base.wrapping_add(packet.gsmart_u16()? as u16);
+            let players = packet.g2()?;
+            match self.worlds.iter_mut().find(|world| world.id == id) {
+                Some(world) => world.players = players,
+                None => return error(format!("Population update for unknown world {}.", id)),
+            }
+        }
+        Ok(full)
+    }
+
+    pub fn get(&self, id: u16) -> Option<&World> {
+        self.worlds.iter().find(|world| world.id == id)
+    }
+
+    fn decode_full(&mut self, packet: &mut Packet) -> Result<(), PacketError> {
+        let country_count = packet.gsmart_u16()?;
+        let mut countries = Vec::with_capacity(country_count);
+        for _ in 0..country_count {
+            countries.push(Country {
+                flag: packet.gsmart_u16()? as u16,
+                name: packet.gjstr()?,
+            });
+        }
+
+        let base = packet.gsmart_u16()? as u16;
+        let _highest = packet.gsmart_u16()?;
+        let world_count = packet.gsmart_u16()?;
+        let mut worlds = Vec::with_capacity(world_count);
+        for _ in 0..world_count {
+            worlds.push(World {
+                id: base.wrapping_add(packet.gsmart_u16()? as u16),
+                country: packet.g1()?,
+                flags: packet.g4()?,
+                activity: packet.gjstr()?,
+                host: packet.gjstr()?,
+                players: 0,
+            });
+        }
+
+        self.checksum = packet.g4()?;
+        self.countries = countries;
+        self.worlds = worlds;
+        Ok(())
+    }
+
+    fn encode_players(&self, packet: &mut Packet, base: u16) {
+        for world in &self.worlds {
+            packet.psmart_u16((world.id - base) as usize);
+            packet.p2(world.players);
+        }
+    }
+
+    /// Returns the lowest world id, which every id is sent relative to.
+    fn base_id(&self) -> u16 {
+        self.worlds.iter().map(|world| world.id).min().unwrap_or(0)
+    }
+}